@@ -0,0 +1,12 @@
+-- db/setup.sql - One-time database setup the API relies on. Run it as the owner of the
+-- goods table: the API connects with least-privilege roles and never changes the schema.
+
+-- Fuzzy matching (fuzzy=true, and the fallback for searches that find nothing) compares
+-- terms with word_similarity from pg_trgm. Without the extension the server starts with
+-- fuzzy matching disabled.
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+-- GET /goods/suggest matches prefixes with lower(column) LIKE 'prefix%'. These indexes let
+-- it read only the matching range instead of every row.
+CREATE INDEX IF NOT EXISTS goods_goods_name_prefix_idx ON goods (lower(goods_name) text_pattern_ops);
+CREATE INDEX IF NOT EXISTS goods_material_code_prefix_idx ON goods (lower(material_code) text_pattern_ops);
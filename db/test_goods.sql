@@ -0,0 +1,23 @@
+-- db/test_goods.sql - The goods table and a few goods for the database tests in
+-- src/tables.rs. Each test gets a scratch database from DATABASE_URL with this file and
+-- db/setup.sql applied; run them with `cargo test -- --ignored`.
+
+CREATE TABLE goods (
+    goods_id SERIAL PRIMARY KEY,
+    material_code TEXT NOT NULL UNIQUE,
+    goods_name TEXT NOT NULL,
+    description TEXT[],
+    price NUMERIC NOT NULL,
+    volumn_l NUMERIC NOT NULL,
+    mass_g NUMERIC NOT NULL,
+    mass_base SMALLINT NOT NULL,
+    volumn_base SMALLINT NOT NULL
+);
+
+INSERT INTO goods (material_code, goods_name, description, price, volumn_l, mass_g, mass_base, volumn_base) VALUES
+    ('TEA-001', 'Green Tea', ARRAY['organic', 'tea', 'organic'], 4.50, 0.25, 100, 1, 1),
+    ('TEA-002', 'Black Tea', ARRAY['tea'], 12.00, 0.25, 250, 1, 1),
+    ('COF-001', 'Coffee Beans', ARRAY['organic', 'fair-trade'], 25.00, 1.00, 1000, 1000, 1),
+    ('COF-002', 'Coffee Filters', NULL, 3.00, 0.50, 50, 1, 1),
+    ('JUI-001', 'Orange Juice', ARRAY['frozen'], 60.00, 2.00, 2100, 1000, 1000),
+    ('WAT-100', '100% Water', '{}', 1.00, 1.50, 1500, 1000, 1000);
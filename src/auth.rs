@@ -0,0 +1,134 @@
+// src/auth.rs
+use crate::error::AppError;
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderValue},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::MethodRouter,
+};
+use sha2::{Digest, Sha256};
+use tracing::warn;
+
+// Require the admin token on a route, sent as `Authorization: Bearer <token>`. Requests
+// without it get a 401 and requests with another token a 403, before the handler reads
+// the body or touches the database. Without a configured token the route is closed.
+pub fn authorized<S>(route: MethodRouter<S>, admin_token: Option<&str>) -> MethodRouter<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    let expected = admin_token.filter(|token| !token.is_empty()).map(token_digest);
+    route.layer(middleware::from_fn_with_state(expected, require_token))
+}
+
+async fn require_token(State(expected): State<Option<[u8; 32]>>, request: Request, next: Next) -> Response {
+    let Some(expected) = expected else {
+        warn!("Rejected {} {}: no admin token is configured", request.method(), request.uri().path());
+        return AppError::Forbidden("This endpoint is disabled: no admin token is configured".to_string())
+            .into_response();
+    };
+
+    match bearer_token(&request) {
+        Some(token) if tokens_match(&token_digest(token), &expected) => next.run(request).await,
+        Some(_) => {
+            warn!("Rejected {} {}: invalid admin token", request.method(), request.uri().path());
+            AppError::Forbidden("Invalid admin token".to_string()).into_response()
+        }
+        None => {
+            let mut response = AppError::Unauthorized("Missing bearer token".to_string()).into_response();
+            response
+                .headers_mut()
+                .insert(header::WWW_AUTHENTICATE, HeaderValue::from_static("Bearer"));
+            response
+        }
+    }
+}
+
+fn bearer_token(request: &Request) -> Option<&str> {
+    let value = request.headers().get(header::AUTHORIZATION)?.to_str().ok()?;
+    let (scheme, token) = value.split_once(' ')?;
+    scheme.eq_ignore_ascii_case("bearer").then(|| token.trim())
+}
+
+fn token_digest(token: &str) -> [u8; 32] {
+    Sha256::digest(token.as_bytes()).into()
+}
+
+// Tokens are compared by digest so both sides have the same length, and every byte is
+// looked at so the time taken does not reveal how much of a guess was right
+fn tokens_match(presented: &[u8; 32], expected: &[u8; 32]) -> bool {
+    let difference = presented.iter().zip(expected).fold(0, |acc, (a, b)| acc | (a ^ b));
+    std::hint::black_box(difference) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{routing::{get, post}, Json, Router};
+
+    async fn serve(app: Router) -> std::net::SocketAddr {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        addr
+    }
+
+    async fn send(addr: std::net::SocketAddr, authorization: Option<&str>, body: &str) -> String {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        let authorization = authorization.map(|value| format!("Authorization: {}\r\n", value)).unwrap_or_default();
+        let request = format!(
+            "POST / HTTP/1.1\r\nHost: x\r\n{}Content-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            authorization,
+            body.len(),
+            body
+        );
+        let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        response.to_lowercase()
+    }
+
+    #[tokio::test]
+    async fn test_write_requires_admin_token() {
+        let write = post(|Json(value): Json<serde_json::Value>| async move { Json(value) });
+        let route = get(|| async { "catalog" }).merge(authorized(write, Some("s3cret")));
+        let addr = serve(Router::new().route("/", route)).await;
+
+        // Credentials are checked before the body is parsed
+        let response = send(addr, None, "not json").await;
+        assert!(response.starts_with("http/1.1 401"));
+        assert!(response.contains("www-authenticate: bearer"));
+        assert!(response.contains("\"code\":\"unauthorized\""));
+
+        for authorization in ["Bearer wrong", "Bearer s3cre", "Basic s3cret"] {
+            let response = send(addr, Some(authorization), "not json").await;
+            let status = if authorization.starts_with("Basic") { "401" } else { "403" };
+            assert!(response.starts_with(&format!("http/1.1 {}", status)), "{}: {}", authorization, response);
+        }
+
+        let response = send(addr, Some("bearer s3cret"), "{\"ok\":true}").await;
+        assert!(response.starts_with("http/1.1 200"));
+        assert!(response.ends_with("{\"ok\":true}"));
+
+        // Reads stay public
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+        stream.write_all(b"GET / HTTP/1.1\r\nHost: x\r\nConnection: close\r\n\r\n").await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 200") && response.ends_with("catalog"));
+    }
+
+    #[tokio::test]
+    async fn test_unconfigured_token_closes_the_route() {
+        for token in [None, Some("")] {
+            let addr = serve(Router::new().route("/", authorized(post(|| async { "created" }), token))).await;
+            for authorization in [None, Some("Bearer "), Some("Bearer anything")] {
+                let response = send(addr, authorization, "").await;
+                assert!(response.starts_with("http/1.1 403"), "{:?}: {}", authorization, response);
+                assert!(!response.contains("created"));
+            }
+        }
+    }
+}
@@ -0,0 +1,281 @@
+// src/cache.rs
+use serde::Serialize;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+// Small in-process cache whose entries expire after a fixed time-to-live. Once full, by
+// entry count or by the estimated size of its entries, expired entries are dropped first
+// and then the oldest ones. Clones share the same entries and counters.
+//
+// Each clear() starts a new generation. A caller that computes a value from the source
+// takes generation() first and passes it to insert(), so a value read before a clear is
+// never cached after it.
+#[derive(Clone)]
+pub struct TtlCache<K, V> {
+    entries: Arc<Mutex<Entries<K, V>>>,
+    hits: Arc<AtomicU64>,
+    misses: Arc<AtomicU64>,
+    ttl: Duration,
+    max_entries: usize,
+    max_bytes: usize,
+    // Estimated memory held by an entry, counted against max_bytes
+    weigh: fn(&K, &V) -> usize,
+}
+
+struct Entries<K, V> {
+    map: HashMap<K, Entry<V>>,
+    bytes: usize,
+    generation: u64,
+}
+
+struct Entry<V> {
+    inserted: Instant,
+    bytes: usize,
+    value: V,
+}
+
+// Snapshot of a cache's size and how often lookups found a fresh entry
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CacheStats {
+    pub entries: usize,
+    pub bytes: usize,
+    pub max_entries: usize,
+    pub max_bytes: usize,
+    pub ttl_secs: u64,
+    pub hits: u64,
+    pub misses: u64,
+}
+
+impl<K, V> TtlCache<K, V>
+where
+    K: Eq + Hash + Clone,
+    V: Clone,
+{
+    pub fn new(ttl: Duration, max_entries: usize) -> Self {
+        Self {
+            entries: Arc::new(Mutex::new(Entries {
+                map: HashMap::new(),
+                bytes: 0,
+                generation: 0,
+            })),
+            hits: Arc::new(AtomicU64::new(0)),
+            misses: Arc::new(AtomicU64::new(0)),
+            ttl,
+            max_entries,
+            max_bytes: usize::MAX,
+            weigh: |_, _| 0,
+        }
+    }
+
+    // Also bound the cache by the total of `weigh` over its entries
+    pub fn with_max_bytes(mut self, max_bytes: usize, weigh: fn(&K, &V) -> usize) -> Self {
+        self.max_bytes = max_bytes;
+        self.weigh = weigh;
+        self
+    }
+
+    pub fn get(&self, key: &K) -> Option<V> {
+        let mut entries = self.lock();
+        let value = match entries.map.get(key) {
+            Some(entry) if entry.inserted.elapsed() < self.ttl => Some(entry.value.clone()),
+            Some(_) => {
+                entries.remove(key);
+                None
+            }
+            None => None,
+        };
+        let counter = if value.is_some() { &self.hits } else { &self.misses };
+        counter.fetch_add(1, Ordering::Relaxed);
+        value
+    }
+
+    // Current generation, to pass to insert once the value is computed
+    pub fn generation(&self) -> u64 {
+        self.lock().generation
+    }
+
+    // Insert unless the cache was cleared since `generation` was taken, in which case the
+    // value may predate the change that cleared it
+    pub fn insert(&self, generation: u64, key: K, value: V) {
+        let bytes = (self.weigh)(&key, &value);
+        if self.max_entries == 0 || bytes > self.max_bytes {
+            return;
+        }
+
+        let mut entries = self.lock();
+        if generation != entries.generation {
+            return;
+        }
+        entries.remove(&key);
+        let full = |entries: &Entries<K, V>| {
+            entries.map.len() >= self.max_entries || entries.bytes + bytes > self.max_bytes
+        };
+        if full(&entries) {
+            let ttl = self.ttl;
+            entries.map.retain(|_, entry| entry.inserted.elapsed() < ttl);
+            entries.bytes = entries.map.values().map(|entry| entry.bytes).sum();
+        }
+        while full(&entries) {
+            let oldest = entries
+                .map
+                .iter()
+                .min_by_key(|(_, entry)| entry.inserted)
+                .map(|(key, _)| key.clone());
+            match oldest {
+                Some(oldest) => entries.remove(&oldest),
+                None => break,
+            }
+        }
+        entries.bytes += bytes;
+        entries.map.insert(
+            key,
+            Entry {
+                inserted: Instant::now(),
+                bytes,
+                value,
+            },
+        );
+    }
+
+    // Drop every entry, returning how many there were; the counters keep running
+    pub fn clear(&self) -> usize {
+        let mut entries = self.lock();
+        let cleared = entries.map.len();
+        entries.map.clear();
+        entries.bytes = 0;
+        entries.generation += 1;
+        cleared
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        let entries = self.lock();
+        CacheStats {
+            entries: entries.map.len(),
+            bytes: entries.bytes,
+            max_entries: self.max_entries,
+            max_bytes: self.max_bytes,
+            ttl_secs: self.ttl.as_secs(),
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Entries<K, V>> {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    #[cfg(test)]
+    fn len(&self) -> usize {
+        self.lock().map.len()
+    }
+}
+
+impl<K: Eq + Hash, V> Entries<K, V> {
+    fn remove(&mut self, key: &K) {
+        if let Some(entry) = self.map.remove(key) {
+            self.bytes -= entry.bytes;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entries_expire() {
+        let cache = TtlCache::new(Duration::from_millis(20), 10);
+        let generation = cache.generation();
+        cache.insert(generation, "sam", 1);
+        assert_eq!(cache.get(&"sam"), Some(1));
+
+        std::thread::sleep(Duration::from_millis(30));
+        assert_eq!(cache.get(&"sam"), None);
+        assert_eq!(cache.len(), 0);
+    }
+
+    #[test]
+    fn test_full_cache_evicts_oldest() {
+        let cache = TtlCache::new(Duration::from_secs(60), 2);
+        let generation = cache.generation();
+        cache.insert(generation, "a", 1);
+        std::thread::sleep(Duration::from_millis(2));
+        cache.insert(generation, "b", 2);
+        std::thread::sleep(Duration::from_millis(2));
+        cache.insert(generation, "c", 3);
+
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get(&"a"), None);
+        assert_eq!(cache.get(&"b"), Some(2));
+        assert_eq!(cache.get(&"c"), Some(3));
+
+        // Replacing an existing key never evicts another one
+        cache.insert(generation, "c", 4);
+        assert_eq!(cache.get(&"b"), Some(2));
+        assert_eq!(cache.get(&"c"), Some(4));
+    }
+
+    #[test]
+    fn test_memory_bound_evicts_oldest() {
+        let cache = TtlCache::new(Duration::from_secs(60), 10)
+            .with_max_bytes(10, |_: &&str, value: &String| value.len());
+        let generation = cache.generation();
+        cache.insert(generation, "a", "aaaa".to_string());
+        std::thread::sleep(Duration::from_millis(2));
+        cache.insert(generation, "b", "bbbb".to_string());
+        std::thread::sleep(Duration::from_millis(2));
+        cache.insert(generation, "c", "cccc".to_string());
+
+        assert_eq!(cache.get(&"a"), None);
+        assert_eq!(cache.stats().bytes, 8);
+
+        // Entries bigger than the whole budget are not cached at all
+        cache.insert(generation, "d", "d".repeat(11));
+        assert_eq!(cache.get(&"d"), None);
+        assert_eq!(cache.len(), 2);
+
+        // Replacing an entry releases the memory of the old value
+        cache.insert(generation, "c", "cc".to_string());
+        assert_eq!(cache.stats().bytes, 6);
+    }
+
+    #[test]
+    fn test_stats_and_clear() {
+        let cache = TtlCache::new(Duration::from_secs(60), 10);
+        let generation = cache.generation();
+        cache.insert(generation, "a", 1);
+        cache.insert(generation, "b", 2);
+        assert_eq!(cache.get(&"a"), Some(1));
+        assert_eq!(cache.get(&"a"), Some(1));
+        assert_eq!(cache.get(&"z"), None);
+
+        let stats = cache.stats();
+        assert_eq!((stats.entries, stats.hits, stats.misses), (2, 2, 1));
+        assert_eq!((stats.max_entries, stats.ttl_secs), (10, 60));
+
+        // Clones share entries and counters
+        assert_eq!(cache.clone().clear(), 2);
+        assert_eq!(cache.get(&"a"), None);
+        let stats = cache.stats();
+        assert_eq!((stats.entries, stats.hits, stats.misses), (0, 2, 2));
+    }
+
+    #[test]
+    fn test_values_read_before_a_clear_are_not_cached() {
+        let cache = TtlCache::new(Duration::from_secs(60), 10);
+
+        // A lookup starts, a write clears the cache, then the lookup finishes
+        let generation = cache.generation();
+        cache.clear();
+        cache.insert(generation, "a", 1);
+        assert_eq!(cache.get(&"a"), None);
+
+        // Lookups that start after the clear are cached as usual
+        let generation = cache.generation();
+        cache.insert(generation, "a", 2);
+        assert_eq!(cache.get(&"a"), Some(2));
+    }
+}
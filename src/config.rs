@@ -1,4 +1,5 @@
 // src/config.rs
+use crate::tables::DEFAULT_FUZZY_THRESHOLD;
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use std::env;
@@ -11,18 +12,147 @@ pub struct DatabaseConfig {
     pub username: String,
     pub password: String,
     pub max_connections: u32,
+    // Privileged credentials for the write API; the read path keeps the least-privilege role above
+    #[serde(default)]
+    pub writer: Option<DatabaseWriterConfig>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatabaseWriterConfig {
+    pub username: String,
+    pub password: String,
+    pub max_connections: u32,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ServerConfig {
     pub host: String,
     pub port: u16,
+    // Reject unrecognized query parameters instead of silently ignoring them
+    #[serde(default = "default_strict_query_params")]
+    pub strict_query_params: bool,
+}
+
+fn default_strict_query_params() -> bool {
+    true
+}
+
+// Credentials for the write and admin endpoints; reads stay public
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AuthConfig {
+    // Bearer token those endpoints require; they are closed while it is unset
+    pub admin_token: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SearchConfig {
+    // Minimum trigram word similarity (0 to 1) for a fuzzy match; lower is more forgiving
+    pub fuzzy_threshold: f32,
+    // Retry searches that match nothing with fuzzy matching, unless the client sends fuzzy=false.
+    // Both need pg_trgm, see db/setup.sql.
+    pub fuzzy_fallback: bool,
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        Self {
+            fuzzy_threshold: DEFAULT_FUZZY_THRESHOLD,
+            fuzzy_fallback: true,
+        }
+    }
+}
+
+// GET /goods/suggest: kept small and fast since it runs on every keystroke
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SuggestConfig {
+    pub default_limit: i64,
+    pub max_limit: i64,
+    // Latency budget; slower lookups are abandoned with a 504
+    pub timeout_ms: u64,
+    pub cache_ttl_secs: u64,
+    pub cache_max_entries: usize,
+}
+
+impl Default for SuggestConfig {
+    fn default() -> Self {
+        Self {
+            default_limit: 10,
+            max_limit: 20,
+            timeout_ms: 250,
+            cache_ttl_secs: 60,
+            cache_max_entries: 1000,
+        }
+    }
+}
+
+// In-memory cache of goods search results, keyed by the normalized search parameters.
+// Writes through this server flush it; changes made elsewhere show up once entries expire.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SearchCacheConfig {
+    pub ttl_secs: u64,
+    // 0 disables the cache
+    pub max_entries: usize,
+    // Budget for the estimated size of the cached pages
+    pub max_bytes: usize,
+}
+
+impl Default for SearchCacheConfig {
+    fn default() -> Self {
+        Self {
+            ttl_secs: 30,
+            max_entries: 1000,
+            max_bytes: 64 * 1024 * 1024,
+        }
+    }
+}
+
+// Cache-Control sent with successful GET responses, per route. Those responses also carry
+// an ETag, so with "no-cache" clients revalidate with If-None-Match. The server still runs
+// the query to answer that; a 304 only saves sending the body again.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CacheControlConfig {
+    // GET /goods searches
+    pub goods: String,
+    // GET /goods/{goods_id} and /goods/by-material/{material_code}
+    pub lookup: String,
+    pub suggest: String,
+    pub tags: String,
+    pub stats: String,
+    pub export: String,
+}
+
+impl Default for CacheControlConfig {
+    fn default() -> Self {
+        Self {
+            goods: "no-cache".to_string(),
+            lookup: "no-cache".to_string(),
+            suggest: "public, max-age=60".to_string(),
+            tags: "public, max-age=300".to_string(),
+            stats: "public, max-age=60".to_string(),
+            export: "no-store".to_string(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AppConfig {
     pub database: DatabaseConfig,
     pub server: ServerConfig,
+    #[serde(default)]
+    pub auth: AuthConfig,
+    #[serde(default)]
+    pub search: SearchConfig,
+    #[serde(default)]
+    pub suggest: SuggestConfig,
+    #[serde(default)]
+    pub search_cache: SearchCacheConfig,
+    #[serde(default)]
+    pub cache_control: CacheControlConfig,
 }
 
 impl AppConfig {
@@ -39,12 +169,94 @@ impl AppConfig {
                     max_connections: env::var("DB_MAX_CONNECTIONS")
                         .unwrap_or_else(|_| "20".to_string())
                         .parse()?,
+                    writer: match env::var("DB_WRITE_USER") {
+                        Ok(username) => Some(DatabaseWriterConfig {
+                            username,
+                            password: env::var("DB_WRITE_PASSWORD")?,
+                            max_connections: env::var("DB_WRITE_MAX_CONNECTIONS")
+                                .unwrap_or_else(|_| "5".to_string())
+                                .parse()?,
+                        }),
+                        Err(_) => None,
+                    },
                 },
                 server: ServerConfig {
                     host: env::var("HOST").unwrap_or_else(|_| "0.0.0.0".to_string()),
                     port: env::var("PORT")
                         .unwrap_or_else(|_| "3000".to_string())
                         .parse()?,
+                    strict_query_params: env::var("STRICT_QUERY_PARAMS")
+                        .unwrap_or_else(|_| "true".to_string())
+                        .parse()?,
+                },
+                auth: AuthConfig {
+                    admin_token: env::var("ADMIN_TOKEN").ok(),
+                },
+                search: {
+                    let defaults = SearchConfig::default();
+                    SearchConfig {
+                        fuzzy_threshold: match env::var("FUZZY_THRESHOLD") {
+                            Ok(threshold) => threshold.parse()?,
+                            Err(_) => defaults.fuzzy_threshold,
+                        },
+                        fuzzy_fallback: match env::var("FUZZY_FALLBACK") {
+                            Ok(fallback) => fallback.parse()?,
+                            Err(_) => defaults.fuzzy_fallback,
+                        },
+                    }
+                },
+                suggest: {
+                    let defaults = SuggestConfig::default();
+                    SuggestConfig {
+                        default_limit: match env::var("SUGGEST_DEFAULT_LIMIT") {
+                            Ok(limit) => limit.parse()?,
+                            Err(_) => defaults.default_limit,
+                        },
+                        max_limit: match env::var("SUGGEST_MAX_LIMIT") {
+                            Ok(limit) => limit.parse()?,
+                            Err(_) => defaults.max_limit,
+                        },
+                        timeout_ms: match env::var("SUGGEST_TIMEOUT_MS") {
+                            Ok(timeout_ms) => timeout_ms.parse()?,
+                            Err(_) => defaults.timeout_ms,
+                        },
+                        cache_ttl_secs: match env::var("SUGGEST_CACHE_TTL_SECS") {
+                            Ok(ttl_secs) => ttl_secs.parse()?,
+                            Err(_) => defaults.cache_ttl_secs,
+                        },
+                        cache_max_entries: match env::var("SUGGEST_CACHE_MAX_ENTRIES") {
+                            Ok(max_entries) => max_entries.parse()?,
+                            Err(_) => defaults.cache_max_entries,
+                        },
+                    }
+                },
+                search_cache: {
+                    let defaults = SearchCacheConfig::default();
+                    SearchCacheConfig {
+                        ttl_secs: match env::var("SEARCH_CACHE_TTL_SECS") {
+                            Ok(ttl_secs) => ttl_secs.parse()?,
+                            Err(_) => defaults.ttl_secs,
+                        },
+                        max_entries: match env::var("SEARCH_CACHE_MAX_ENTRIES") {
+                            Ok(max_entries) => max_entries.parse()?,
+                            Err(_) => defaults.max_entries,
+                        },
+                        max_bytes: match env::var("SEARCH_CACHE_MAX_BYTES") {
+                            Ok(max_bytes) => max_bytes.parse()?,
+                            Err(_) => defaults.max_bytes,
+                        },
+                    }
+                },
+                cache_control: {
+                    let defaults = CacheControlConfig::default();
+                    CacheControlConfig {
+                        goods: env::var("CACHE_CONTROL_GOODS").unwrap_or(defaults.goods),
+                        lookup: env::var("CACHE_CONTROL_LOOKUP").unwrap_or(defaults.lookup),
+                        suggest: env::var("CACHE_CONTROL_SUGGEST").unwrap_or(defaults.suggest),
+                        tags: env::var("CACHE_CONTROL_TAGS").unwrap_or(defaults.tags),
+                        stats: env::var("CACHE_CONTROL_STATS").unwrap_or(defaults.stats),
+                        export: env::var("CACHE_CONTROL_EXPORT").unwrap_or(defaults.export),
+                    }
                 },
             });
         }
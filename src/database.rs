@@ -4,7 +4,7 @@ use crate::tables::GoodsTable;
 use anyhow::Result;
 use sqlx::{postgres::PgPoolOptions, PgPool};
 use std::time::Duration;
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
 #[derive(Clone)]
 pub struct Database {
@@ -15,24 +15,8 @@ pub struct Database {
 impl Database {
     pub async fn new(config: DatabaseConfig) -> Result<Self> {
         info!("Connecting to database...");
-        
-        let database_url = format!(
-            "postgres://{}:{}@{}:{}/{}",
-            config.username, config.password, config.host, config.port, config.dbname
-        );
 
-        // Create connection pool with proper configuration
-        let pool = PgPoolOptions::new()
-            .max_connections(config.max_connections)
-            .acquire_timeout(Duration::from_secs(10))
-            .idle_timeout(Duration::from_secs(600))
-            .max_lifetime(Duration::from_secs(1800))
-            .connect(&database_url)
-            .await
-            .map_err(|e| {
-                error!("Failed to connect to database: {}", e);
-                e
-            })?;
+        let pool = Self::connect(&config, &config.username, &config.password, config.max_connections).await?;
 
         info!("Database connection pool created with {} max connections", config.max_connections);
 
@@ -47,23 +31,64 @@ impl Database {
 
         info!("Database connection verified");
 
+        // Writes use their own privileged pool so the read path keeps its least-privilege role
+        let write_pool = match &config.writer {
+            Some(writer) => {
+                let write_pool = Self::connect(&config, &writer.username, &writer.password, writer.max_connections).await?;
+                info!("Database write pool created with {} max connections", writer.max_connections);
+                Some(write_pool)
+            }
+            None => {
+                info!("No database writer configured, write endpoints are disabled");
+                None
+            }
+        };
+
         // Initialize tables
-        let goods_table = GoodsTable::new(pool.clone());
-        
+        let mut goods_table = GoodsTable::new(pool.clone(), write_pool);
+
         // Verify table access instead of trying to create tables
         goods_table.verify_table_access().await?;
         info!("Database table access verified");
 
+        // The read-only role cannot install extensions, see db/setup.sql
+        if !goods_table.has_trigram_extension().await? {
+            warn!("The pg_trgm extension is not installed, fuzzy matching is disabled");
+            goods_table = goods_table.without_fuzzy_matching();
+        }
+
         Ok(Self {
             pool,
             goods_table,
         })
     }
 
+    async fn connect(config: &DatabaseConfig, username: &str, password: &str, max_connections: u32) -> Result<PgPool> {
+        let database_url = format!(
+            "postgres://{}:{}@{}:{}/{}",
+            username, password, config.host, config.port, config.dbname
+        );
+
+        // Create connection pool with proper configuration
+        let pool = PgPoolOptions::new()
+            .max_connections(max_connections)
+            .acquire_timeout(Duration::from_secs(10))
+            .idle_timeout(Duration::from_secs(600))
+            .max_lifetime(Duration::from_secs(1800))
+            .connect(&database_url)
+            .await
+            .map_err(|e| {
+                error!("Failed to connect to database as {}: {}", username, e);
+                e
+            })?;
+
+        Ok(pool)
+    }
+
     pub async fn health_check(&self) -> Result<(), sqlx::Error> {
         sqlx::query("SELECT 1")
             .execute(&self.pool)
             .await
             .map(|_| ())
     }
-}
\ No newline at end of file
+}
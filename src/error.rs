@@ -0,0 +1,200 @@
+// src/error.rs
+use crate::response::ErrorResponse;
+use axum::{
+    extract::rejection::JsonRejection,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tracing::{error, warn};
+
+// Every error a handler can return. Each variant maps to an HTTP status and a stable,
+// machine-readable `code` in the JSON body so clients can branch without parsing messages.
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum AppError {
+    #[error("{0}")]
+    Validation(String),
+    // One or more invalid request parameters, each reported separately
+    #[error("Invalid query parameters: {}", summarize(.0))]
+    InvalidFields(Vec<FieldError>),
+    // No credentials were sent
+    #[error("{0}")]
+    Unauthorized(String),
+    // Credentials were sent but do not allow the request
+    #[error("{0}")]
+    Forbidden(String),
+    #[error("{0}")]
+    NotFound(String),
+    #[error("{0}")]
+    Conflict(String),
+    // None of the media types the client accepts can be produced
+    #[error("{0}")]
+    NotAcceptable(String),
+    #[error("{0}")]
+    DatabaseUnavailable(String),
+    #[error("{0}")]
+    Timeout(String),
+    #[error("{0}")]
+    Internal(String),
+}
+
+// A single invalid parameter. `code` is stable and machine-readable, like AppError::code.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldError {
+    pub field: String,
+    pub code: String,
+    pub message: String,
+    pub received_value: Option<String>,
+}
+
+fn summarize(errors: &[FieldError]) -> String {
+    errors
+        .iter()
+        .map(|error| error.message.as_str())
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+impl AppError {
+    pub fn validation(message: impl Into<String>) -> Self {
+        AppError::Validation(message.into())
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        AppError::NotFound(message.into())
+    }
+
+    pub fn status(&self) -> StatusCode {
+        match self {
+            AppError::Validation(_) | AppError::InvalidFields(_) => StatusCode::BAD_REQUEST,
+            AppError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            AppError::Forbidden(_) => StatusCode::FORBIDDEN,
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            AppError::Conflict(_) => StatusCode::CONFLICT,
+            AppError::NotAcceptable(_) => StatusCode::NOT_ACCEPTABLE,
+            AppError::DatabaseUnavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+            AppError::Timeout(_) => StatusCode::GATEWAY_TIMEOUT,
+            AppError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::Validation(_) | AppError::InvalidFields(_) => "validation_error",
+            AppError::Unauthorized(_) => "unauthorized",
+            AppError::Forbidden(_) => "forbidden",
+            AppError::NotFound(_) => "not_found",
+            AppError::Conflict(_) => "conflict",
+            AppError::NotAcceptable(_) => "not_acceptable",
+            AppError::DatabaseUnavailable(_) => "database_unavailable",
+            AppError::Timeout(_) => "timeout",
+            AppError::Internal(_) => "internal_error",
+        }
+    }
+
+    // Classify a database error, logging the details that are not sent to the client
+    pub fn database(e: sqlx::Error, action: &str) -> Self {
+        match &e {
+            sqlx::Error::Database(db_error) => match db_error.code().as_deref() {
+                // unique_violation
+                Some("23505") => {
+                    warn!("Conflict during {}: {}", action, db_error);
+                    return AppError::Conflict("A good with these values already exists".to_string());
+                }
+                // Constraint violations: the Postgres message names constraints and columns of
+                // the schema, so it is only logged and the client gets a fixed message
+                Some(code @ ("23502" | "23503" | "23514" | "22003")) => {
+                    warn!("Constraint violation during {}: {}", action, db_error);
+                    let column = db_error
+                        .try_downcast_ref::<sqlx::postgres::PgDatabaseError>()
+                        .and_then(|db_error| db_error.column());
+                    let message = match (code, column) {
+                        // not_null_violation
+                        ("23502", Some(column)) => format!("Invalid values: '{}' is required", column),
+                        ("23502", None) => "Invalid values: a required field is missing".to_string(),
+                        // foreign_key_violation
+                        ("23503", _) => "Invalid values: a referenced record does not exist".to_string(),
+                        // check_violation
+                        ("23514", _) => "Invalid values: a value is outside its allowed range".to_string(),
+                        // numeric_value_out_of_range
+                        _ => "Invalid values: a number is too large".to_string(),
+                    };
+                    return AppError::Validation(message);
+                }
+                // query_canceled, raised by statement_timeout
+                Some("57014") => {
+                    warn!("Query timed out during {}: {}", action, db_error);
+                    return AppError::Timeout(format!("Timed out while trying to {}", action));
+                }
+                _ => {}
+            },
+            sqlx::Error::PoolTimedOut
+            | sqlx::Error::PoolClosed
+            | sqlx::Error::Io(_)
+            | sqlx::Error::Tls(_) => {
+                error!("Database unavailable during {}: {}", action, e);
+                return AppError::DatabaseUnavailable("Database is unavailable, try again later".to_string());
+            }
+            _ => {}
+        }
+
+        error!("Database error during {}: {}", action, e);
+        AppError::Internal(format!("Failed to {}", action))
+    }
+}
+
+impl From<JsonRejection> for AppError {
+    fn from(rejection: JsonRejection) -> Self {
+        AppError::Validation(format!("Invalid request body: {}", rejection.body_text()))
+    }
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        let status = self.status();
+        let mut error_response = ErrorResponse::new(self.code(), &self.to_string());
+        if let AppError::InvalidFields(errors) = self {
+            error_response.errors = errors;
+        }
+        (status, Json(error_response)).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_and_code_mapping() {
+        let cases = [
+            (AppError::validation("bad"), StatusCode::BAD_REQUEST, "validation_error"),
+            (AppError::Unauthorized("who".to_string()), StatusCode::UNAUTHORIZED, "unauthorized"),
+            (AppError::Forbidden("no".to_string()), StatusCode::FORBIDDEN, "forbidden"),
+            (AppError::not_found("missing"), StatusCode::NOT_FOUND, "not_found"),
+            (AppError::Conflict("dup".to_string()), StatusCode::CONFLICT, "conflict"),
+            (AppError::NotAcceptable("xml".to_string()), StatusCode::NOT_ACCEPTABLE, "not_acceptable"),
+            (AppError::DatabaseUnavailable("down".to_string()), StatusCode::SERVICE_UNAVAILABLE, "database_unavailable"),
+            (AppError::Timeout("slow".to_string()), StatusCode::GATEWAY_TIMEOUT, "timeout"),
+            (AppError::Internal("oops".to_string()), StatusCode::INTERNAL_SERVER_ERROR, "internal_error"),
+        ];
+        for (error, status, code) in cases {
+            assert_eq!(error.status(), status);
+            assert_eq!(error.code(), code);
+            assert_eq!(error.into_response().status(), status);
+        }
+    }
+
+    #[test]
+    fn test_database_error_classification() {
+        assert_eq!(
+            AppError::database(sqlx::Error::PoolTimedOut, "search goods").code(),
+            "database_unavailable"
+        );
+        assert_eq!(
+            AppError::database(sqlx::Error::RowNotFound, "search goods"),
+            AppError::Internal("Failed to search goods".to_string())
+        );
+    }
+}
@@ -0,0 +1,371 @@
+// src/format.rs
+use crate::error::AppError;
+use crate::response::ApiResponse;
+use axum::{
+    body::{Body, Bytes},
+    http::{header, HeaderMap, HeaderValue},
+    response::{IntoResponse, Response},
+};
+use futures::{Stream, StreamExt};
+use serde::Serialize;
+
+// Media types accepted for each format, preferred spelling first
+const JSON_TYPES: [&str; 1] = ["application/json"];
+const CSV_TYPES: [&str; 1] = ["text/csv"];
+const NDJSON_TYPES: [&str; 2] = ["application/x-ndjson", "application/ndjson"];
+const MSGPACK_TYPES: [&str; 3] = ["application/msgpack", "application/x-msgpack", "application/vnd.msgpack"];
+
+// Streamed bodies are sent in chunks of about this size rather than one per row
+const STREAM_CHUNK_BYTES: usize = 64 * 1024;
+
+// Values of the format= override
+pub const FORMAT_NAMES: [&str; 5] = ["envelope", "json", "csv", "ndjson", "msgpack"];
+
+// Value of format= among query parameters; the last one wins like other parameters
+pub fn format_param(params: &[(String, String)]) -> Option<&str> {
+    params
+        .iter()
+        .rev()
+        .find(|(key, _)| key == "format")
+        .map(|(_, value)| value.as_str())
+}
+
+// How a list of goods is written. `Envelope` is the usual ApiResponse JSON and what
+// application/json asks for; the bare JSON array is only available through format=json.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ResponseFormat {
+    #[default]
+    Envelope,
+    Json,
+    Csv,
+    Ndjson,
+    MessagePack,
+}
+
+impl ResponseFormat {
+    // Pick the format from format= when given, otherwise from the Accept header
+    pub fn negotiate(headers: &HeaderMap, format: Option<&str>) -> Result<Self, AppError> {
+        if let Some(format) = format {
+            return Self::from_name(format).ok_or_else(|| {
+                AppError::NotAcceptable(format!(
+                    "Unsupported format '{}'. Supported formats: {}",
+                    format,
+                    FORMAT_NAMES.join(", ")
+                ))
+            });
+        }
+
+        let accept = headers
+            .get_all(header::ACCEPT)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .collect::<Vec<_>>()
+            .join(",");
+        Self::from_accept(&accept)
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "envelope" => Some(ResponseFormat::Envelope),
+            "json" => Some(ResponseFormat::Json),
+            "csv" => Some(ResponseFormat::Csv),
+            "ndjson" => Some(ResponseFormat::Ndjson),
+            "msgpack" | "messagepack" => Some(ResponseFormat::MessagePack),
+            _ => None,
+        }
+    }
+
+    // Best supported media range of an Accept header, by quality and then by position.
+    // A missing or empty header accepts anything.
+    fn from_accept(accept: &str) -> Result<Self, AppError> {
+        if accept.trim().is_empty() {
+            return Ok(ResponseFormat::Envelope);
+        }
+
+        let mut ranges: Vec<(f32, &str)> = Vec::new();
+        for range in accept.split(',') {
+            let mut parts = range.split(';').map(str::trim);
+            let media_type = parts.next().unwrap_or_default();
+            if media_type.is_empty() {
+                continue;
+            }
+            let quality = parts
+                .filter_map(|param| param.strip_prefix("q="))
+                .find_map(|q| q.parse::<f32>().ok())
+                .unwrap_or(1.0);
+            if quality > 0.0 {
+                ranges.push((quality, media_type));
+            }
+        }
+        // Stable, so equally preferred ranges keep the client's order
+        ranges.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+        ranges
+            .iter()
+            .find_map(|(_, media_type)| Self::from_media_type(media_type))
+            .ok_or_else(|| {
+                AppError::NotAcceptable(format!(
+                    "None of the accepted media types can be produced. Supported types: {}",
+                    [&JSON_TYPES[..], &CSV_TYPES[..], &NDJSON_TYPES[..], &MSGPACK_TYPES[..]].concat().join(", ")
+                ))
+            })
+    }
+
+    fn from_media_type(media_type: &str) -> Option<Self> {
+        let media_type = media_type.to_ascii_lowercase();
+        match media_type.as_str() {
+            "*/*" | "application/*" => Some(ResponseFormat::Envelope),
+            "text/*" => Some(ResponseFormat::Csv),
+            t if JSON_TYPES.contains(&t) => Some(ResponseFormat::Envelope),
+            t if CSV_TYPES.contains(&t) => Some(ResponseFormat::Csv),
+            t if NDJSON_TYPES.contains(&t) => Some(ResponseFormat::Ndjson),
+            t if MSGPACK_TYPES.contains(&t) => Some(ResponseFormat::MessagePack),
+            _ => None,
+        }
+    }
+
+    fn content_type(&self) -> &'static str {
+        match self {
+            ResponseFormat::Envelope | ResponseFormat::Json => "application/json",
+            ResponseFormat::Csv => "text/csv; charset=utf-8",
+            ResponseFormat::Ndjson => "application/x-ndjson",
+            ResponseFormat::MessagePack => "application/msgpack",
+        }
+    }
+
+    // Send a list response in this format. Without the envelope, the total count and next
+    // cursor travel in the X-Total-Count and X-Next-Cursor headers.
+    pub fn respond<T: Serialize>(&self, envelope: ApiResponse<Vec<T>>, columns: &[String]) -> Result<Response, AppError> {
+        let mut response = match self {
+            ResponseFormat::Envelope => envelope.into_response(),
+            format => {
+                let mut response = format.render(envelope.data.as_deref().unwrap_or_default(), columns)?;
+                if let Some(pagination) = &envelope.pagination {
+                    let headers = response.headers_mut();
+                    headers.insert("x-total-count", HeaderValue::from(pagination.total_count));
+                    if let Some(cursor) = &pagination.next_cursor
+                        && let Ok(value) = HeaderValue::from_str(cursor)
+                    {
+                        headers.insert("x-next-cursor", value);
+                    }
+                }
+                response
+            }
+        };
+        response.headers_mut().insert(header::VARY, HeaderValue::from_static("accept"));
+        Ok(response)
+    }
+
+    // Write `items` without the envelope. CSV has one column per entry of `columns`, in
+    // that order, and a header row naming them.
+    pub fn render<T: Serialize>(&self, items: &[T], columns: &[String]) -> Result<Response, AppError> {
+        let body = match self {
+            ResponseFormat::Envelope | ResponseFormat::Json => serde_json::to_vec(items).map_err(render_error)?,
+            ResponseFormat::Ndjson => {
+                let mut body = Vec::new();
+                for item in items {
+                    serde_json::to_writer(&mut body, item).map_err(render_error)?;
+                    body.push(b'\n');
+                }
+                body
+            }
+            ResponseFormat::MessagePack => rmp_serde::to_vec_named(items).map_err(render_error)?,
+            ResponseFormat::Csv => render_csv(items, columns)?,
+        };
+
+        let mut response = body.into_response();
+        response
+            .headers_mut()
+            .insert(header::CONTENT_TYPE, HeaderValue::from_static(self.content_type()));
+        Ok(response)
+    }
+
+    // This format if it can be streamed, so the choice can be checked before any work
+    pub fn streamable(self) -> Result<Self, AppError> {
+        match self {
+            ResponseFormat::Envelope | ResponseFormat::Json | ResponseFormat::Ndjson => Ok(self),
+            _ => Err(AppError::NotAcceptable(
+                "Streamed responses are only available as JSON or NDJSON".to_string(),
+            )),
+        }
+    }
+
+    // Send items as they arrive, as a chunked JSON array or as NDJSON; other formats
+    // cannot be written incrementally. The status is sent before the first row, so an
+    // error part way through can only cut the body short.
+    pub fn stream<T, S>(&self, items: S) -> Result<Response, AppError>
+    where
+        T: Serialize + Send + 'static,
+        S: Stream<Item = Result<T, AppError>> + Send + 'static,
+    {
+        let array = self.streamable()? != ResponseFormat::Ndjson;
+
+        let body = async_stream::stream! {
+            let mut items = std::pin::pin!(items);
+            let mut chunk = Vec::with_capacity(STREAM_CHUNK_BYTES);
+            let mut first = true;
+            if array {
+                chunk.push(b'[');
+            }
+            while let Some(item) = items.next().await {
+                if array && !first {
+                    chunk.push(b',');
+                }
+                first = false;
+                if let Err(e) = item.and_then(|item| serde_json::to_writer(&mut chunk, &item).map_err(render_error)) {
+                    yield Err(e);
+                    return;
+                }
+                if !array {
+                    chunk.push(b'\n');
+                }
+                if chunk.len() >= STREAM_CHUNK_BYTES {
+                    yield Ok(Bytes::from(std::mem::replace(&mut chunk, Vec::with_capacity(STREAM_CHUNK_BYTES))));
+                }
+            }
+            if array {
+                chunk.push(b']');
+            }
+            yield Ok::<_, AppError>(Bytes::from(chunk));
+        };
+
+        let mut response = Body::from_stream(body).into_response();
+        let headers = response.headers_mut();
+        headers.insert(header::CONTENT_TYPE, HeaderValue::from_static(self.content_type()));
+        headers.insert(header::VARY, HeaderValue::from_static("accept"));
+        Ok(response)
+    }
+}
+
+fn render_csv<T: Serialize>(items: &[T], columns: &[String]) -> Result<Vec<u8>, AppError> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record(columns).map_err(render_error)?;
+    for item in items {
+        let values = match serde_json::to_value(item).map_err(render_error)? {
+            serde_json::Value::Object(values) => values,
+            _ => serde_json::Map::new(),
+        };
+        let row = columns.iter().map(|column| csv_cell(values.get(column)));
+        writer.write_record(row).map_err(render_error)?;
+    }
+    writer.into_inner().map_err(|e| render_error(e.error()))
+}
+
+// Spreadsheet-friendly cell: text as is, lists such as description joined by "; ", and
+// missing values empty
+fn csv_cell(value: Option<&serde_json::Value>) -> String {
+    match value {
+        None | Some(serde_json::Value::Null) => String::new(),
+        Some(serde_json::Value::String(text)) => text.clone(),
+        Some(serde_json::Value::Array(items)) => items
+            .iter()
+            .map(|item| csv_cell(Some(item)))
+            .collect::<Vec<_>>()
+            .join("; "),
+        Some(other) => other.to_string(),
+    }
+}
+
+fn render_error(e: impl std::fmt::Display) -> AppError {
+    tracing::error!("Failed to render response: {}", e);
+    AppError::Internal("Failed to render response".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn accept(value: &str) -> Result<ResponseFormat, AppError> {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::ACCEPT, HeaderValue::from_str(value).unwrap());
+        ResponseFormat::negotiate(&headers, None)
+    }
+
+    #[test]
+    fn test_negotiation() {
+        assert_eq!(ResponseFormat::negotiate(&HeaderMap::new(), None).unwrap(), ResponseFormat::Envelope);
+        assert_eq!(accept("application/json").unwrap(), ResponseFormat::Envelope);
+        assert_eq!(accept("*/*").unwrap(), ResponseFormat::Envelope);
+        assert_eq!(accept("text/csv").unwrap(), ResponseFormat::Csv);
+        assert_eq!(accept("application/x-ndjson").unwrap(), ResponseFormat::Ndjson);
+        assert_eq!(accept("application/msgpack").unwrap(), ResponseFormat::MessagePack);
+
+        // Highest quality wins, then the first listed; unsupported ranges are skipped
+        assert_eq!(accept("application/json;q=0.5, text/csv").unwrap(), ResponseFormat::Csv);
+        assert_eq!(accept("text/html, application/x-ndjson, text/csv").unwrap(), ResponseFormat::Ndjson);
+        assert_eq!(accept("text/csv;q=0, */*;q=0.1").unwrap(), ResponseFormat::Envelope);
+
+        let error = accept("text/html, application/xml").unwrap_err();
+        assert_eq!(error.status(), axum::http::StatusCode::NOT_ACCEPTABLE);
+        assert!(accept("text/csv;q=0").is_err());
+
+        // format= overrides the Accept header
+        let mut headers = HeaderMap::new();
+        headers.insert(header::ACCEPT, HeaderValue::from_static("text/csv"));
+        assert_eq!(ResponseFormat::negotiate(&headers, Some("json")).unwrap(), ResponseFormat::Json);
+        assert_eq!(ResponseFormat::negotiate(&headers, Some("MSGPACK")).unwrap(), ResponseFormat::MessagePack);
+        assert_eq!(ResponseFormat::negotiate(&headers, Some("xml")).unwrap_err().code(), "not_acceptable");
+    }
+
+    async fn body(response: Response) -> Vec<u8> {
+        axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap().to_vec()
+    }
+
+    #[tokio::test]
+    async fn test_rendering() {
+        let items = vec![
+            serde_json::json!({"goods_id": 1, "goods_name": "Tea, green", "description": ["organic", "loose"]}),
+            serde_json::json!({"goods_id": 2, "goods_name": "Coffee", "description": null}),
+        ];
+        let columns = vec!["goods_id".to_string(), "goods_name".to_string(), "description".to_string()];
+
+        let response = ResponseFormat::Csv.render(&items, &columns).unwrap();
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "text/csv; charset=utf-8");
+        assert_eq!(
+            String::from_utf8(body(response).await).unwrap(),
+            "goods_id,goods_name,description\n1,\"Tea, green\",organic; loose\n2,Coffee,\n"
+        );
+
+        let response = ResponseFormat::Ndjson.render(&items, &columns).unwrap();
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "application/x-ndjson");
+        let lines: Vec<serde_json::Value> = String::from_utf8(body(response).await)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines, items);
+
+        let response = ResponseFormat::MessagePack.render(&items, &columns).unwrap();
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "application/msgpack");
+        let decoded: Vec<serde_json::Value> = rmp_serde::from_slice(&body(response).await).unwrap();
+        assert_eq!(decoded, items);
+
+        let response = ResponseFormat::Json.render(&items, &columns).unwrap();
+        assert_eq!(serde_json::from_slice::<serde_json::Value>(&body(response).await).unwrap(), serde_json::json!(items));
+    }
+
+    #[tokio::test]
+    async fn test_streaming() {
+        let items = || futures::stream::iter((1..=3).map(|id| Ok(serde_json::json!({ "goods_id": id }))));
+
+        let response = ResponseFormat::Envelope.stream(items()).unwrap();
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "application/json");
+        assert_eq!(body(response).await, br#"[{"goods_id":1},{"goods_id":2},{"goods_id":3}]"#);
+
+        let response = ResponseFormat::Ndjson.stream(items()).unwrap();
+        assert_eq!(body(response).await, b"{\"goods_id\":1}\n{\"goods_id\":2}\n{\"goods_id\":3}\n");
+
+        let empty = futures::stream::iter(Vec::<Result<serde_json::Value, AppError>>::new());
+        assert_eq!(body(ResponseFormat::Json.stream(empty).unwrap()).await, b"[]");
+
+        // A failure part way through cuts the body short instead of closing the array
+        let failing = futures::stream::iter(vec![
+            Ok(serde_json::json!({ "goods_id": 1 })),
+            Err(AppError::Internal("Failed to export goods".to_string())),
+        ]);
+        let response = ResponseFormat::Json.stream(failing).unwrap();
+        assert!(axum::body::to_bytes(response.into_body(), usize::MAX).await.is_err());
+
+        assert_eq!(ResponseFormat::Csv.stream(items()).unwrap_err().code(), "not_acceptable");
+    }
+}
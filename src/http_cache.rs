@@ -0,0 +1,171 @@
+// src/http_cache.rs
+use crate::error::AppError;
+use axum::{
+    body::{Body, Bytes, HttpBody},
+    extract::{Request, State},
+    http::{header, HeaderValue, Method, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::MethodRouter,
+};
+use sha2::{Digest, Sha256};
+use tracing::warn;
+
+// Largest body buffered to compute an ETag; bigger and streamed responses get none
+const MAX_ETAG_BODY_BYTES: u64 = 8 * 1024 * 1024;
+
+// Add conditional GET support to a route: successful GET responses get `cache_control`
+// and a weak ETag, and requests whose If-None-Match matches it get an empty 304. The
+// handler still runs in full, database query included, before the ETag is known, so a
+// 304 saves the transfer but not the work. Other methods pass through untouched.
+pub fn cached<S>(route: MethodRouter<S>, cache_control: &str) -> MethodRouter<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    let cache_control = HeaderValue::from_str(cache_control).unwrap_or_else(|_| {
+        warn!("Invalid Cache-Control value '{}', using no-cache", cache_control);
+        HeaderValue::from_static("no-cache")
+    });
+    route.layer(middleware::from_fn_with_state(cache_control, conditional_get))
+}
+
+async fn conditional_get(State(cache_control): State<HeaderValue>, request: Request, next: Next) -> Response {
+    if request.method() != Method::GET && request.method() != Method::HEAD {
+        return next.run(request).await;
+    }
+    let if_none_match = request.headers().get(header::IF_NONE_MATCH).cloned();
+
+    let response = next.run(request).await;
+    if response.status() != StatusCode::OK {
+        return response;
+    }
+    let (mut parts, body) = response.into_parts();
+    parts.headers.insert(header::CACHE_CONTROL, cache_control);
+
+    // Streamed bodies are sent as they are produced, so they cannot be hashed first
+    if body.size_hint().exact().is_none_or(|size| size > MAX_ETAG_BODY_BYTES) {
+        return Response::from_parts(parts, body);
+    }
+    let bytes = match axum::body::to_bytes(body, MAX_ETAG_BODY_BYTES as usize).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            warn!("Failed to buffer response for its ETag: {}", e);
+            return AppError::Internal("Failed to send response".to_string()).into_response();
+        }
+    };
+
+    let etag = entity_tag(parts.headers.get(header::CONTENT_TYPE), &bytes);
+    parts.headers.insert(header::ETAG, etag.clone());
+    if if_none_match.is_some_and(|value| etag_matches(&value, &etag)) {
+        parts.status = StatusCode::NOT_MODIFIED;
+        parts.headers.remove(header::CONTENT_TYPE);
+        parts.headers.remove(header::CONTENT_LENGTH);
+        return Response::from_parts(parts, Body::empty());
+    }
+    Response::from_parts(parts, Body::from(bytes))
+}
+
+// Weak ETag of a representation: a hash of its media type and content. The timestamp
+// of the JSON envelope changes on every response, so it is left out of the hash, and
+// responses that differ only in it are equivalent but not byte-for-byte identical.
+fn entity_tag(content_type: Option<&HeaderValue>, body: &Bytes) -> HeaderValue {
+    let content_type = content_type.map(HeaderValue::as_bytes).unwrap_or_default();
+    let mut hasher = Sha256::new();
+    hasher.update(content_type);
+    hasher.update([0]);
+
+    let envelope = content_type
+        .starts_with(b"application/json")
+        .then(|| serde_json::from_slice::<serde_json::Value>(body).ok())
+        .flatten();
+    match envelope {
+        Some(serde_json::Value::Object(mut values)) if values.contains_key("timestamp") => {
+            values.remove("timestamp");
+            hasher.update(serde_json::to_vec(&values).unwrap_or_default());
+        }
+        _ => hasher.update(body),
+    }
+
+    let digest: String = hasher.finalize()[..16].iter().map(|b| format!("{:02x}", b)).collect();
+    HeaderValue::from_str(&format!("W/\"{}\"", digest)).expect("hex digest is a valid header value")
+}
+
+// If-None-Match uses the weak comparison, so W/ prefixes are ignored
+fn etag_matches(if_none_match: &HeaderValue, etag: &HeaderValue) -> bool {
+    let (Ok(if_none_match), Ok(etag)) = (if_none_match.to_str(), etag.to_str()) else {
+        return false;
+    };
+    let opaque = |tag: &str| tag.strip_prefix("W/").unwrap_or(tag).to_string();
+    let etag = opaque(etag);
+    if_none_match
+        .split(',')
+        .map(str::trim)
+        .any(|candidate| candidate == "*" || opaque(candidate) == etag)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{routing::get, Router};
+
+    #[test]
+    fn test_entity_tags() {
+        let json = HeaderValue::from_static("application/json");
+        let first = Bytes::from_static(br#"{"success":true,"data":[1,2],"timestamp":"2026-10-17T10:00:00Z"}"#);
+        let later = Bytes::from_static(br#"{"success":true,"data":[1,2],"timestamp":"2026-10-17T10:05:00Z"}"#);
+        let changed = Bytes::from_static(br#"{"success":true,"data":[1,3],"timestamp":"2026-10-17T10:05:00Z"}"#);
+
+        let etag = entity_tag(Some(&json), &first);
+        assert!(etag.to_str().unwrap().starts_with("W/\""));
+        assert_eq!(etag.len(), 36);
+        assert_eq!(etag, entity_tag(Some(&json), &later));
+        assert_ne!(etag, entity_tag(Some(&json), &changed));
+        assert_ne!(etag, entity_tag(Some(&HeaderValue::from_static("text/csv")), &first));
+
+        assert!(etag_matches(&etag, &etag));
+        let list = HeaderValue::from_str(&format!("\"other\", {}", etag.to_str().unwrap())).unwrap();
+        assert!(etag_matches(&list, &etag));
+        let strong = HeaderValue::from_str(etag.to_str().unwrap().strip_prefix("W/").unwrap()).unwrap();
+        assert!(etag_matches(&strong, &etag));
+        assert!(etag_matches(&HeaderValue::from_static("*"), &etag));
+        assert!(!etag_matches(&HeaderValue::from_static("\"other\""), &etag));
+    }
+
+    #[tokio::test]
+    async fn test_conditional_get() {
+        let app: Router = Router::new().route("/", cached(get(|| async { "catalog" }).post(|| async { "created" }), "public, max-age=60"));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let send = |request: String| async move {
+            use tokio::io::{AsyncReadExt, AsyncWriteExt};
+            let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+            stream.write_all(request.as_bytes()).await.unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).await.unwrap();
+            response.to_lowercase()
+        };
+
+        let response = send("GET / HTTP/1.1\r\nHost: x\r\nConnection: close\r\n\r\n".to_string()).await;
+        assert!(response.starts_with("http/1.1 200"));
+        assert!(response.contains("cache-control: public, max-age=60"));
+        let etag = response
+            .lines()
+            .find_map(|line| line.strip_prefix("etag: w/"))
+            .unwrap()
+            .to_string();
+        let etag = format!("W/{}", etag);
+
+        let request = format!("GET / HTTP/1.1\r\nHost: x\r\nIf-None-Match: {}\r\nConnection: close\r\n\r\n", etag);
+        let response = send(request).await;
+        assert!(response.starts_with("http/1.1 304"));
+        assert!(response.contains(&format!("etag: {}", etag.to_lowercase())));
+        assert!(!response.contains("catalog"));
+
+        let request = "POST / HTTP/1.1\r\nHost: x\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string();
+        let response = send(request).await;
+        assert!(response.starts_with("http/1.1 200"));
+        assert!(!response.contains("etag:") && !response.contains("cache-control:"));
+    }
+}
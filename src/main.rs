@@ -1,17 +1,22 @@
 // src/main.rs
+mod auth;
+mod cache;
 mod config;
 mod database;
+mod error;
+mod format;
+mod http_cache;
 mod request;
 mod response;
 mod server;
 mod tables;
+mod v2;
 
 use anyhow::Result;
 use config::AppConfig;
 use database::Database;
 use server::Server;
 use tracing::{info, Level};
-use tracing_subscriber;
 
 #[tokio::main]
 async fn main() -> Result<()> {
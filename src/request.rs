@@ -1,15 +1,22 @@
 // src/request.rs
-use crate::tables::GoodsSearchParams;
+use crate::error::{AppError, FieldError};
+use crate::tables::{
+    Facet, FilterExpr, FilterOp, FuzzyMode, Good, GoodPatch, GoodsColumn, GoodsSearchParams, Keyset,
+    MatchMode, NewGood, SortKey, SortValue, TagMode, TextMatch, DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE,
+};
 use axum::extract::Query;
-use serde::{Deserialize, Serialize};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Deserializer, Serialize};
 use std::collections::HashMap;
 use std::str::FromStr;
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct GoodsQueryParams {
     pub goods_id: Option<String>,
     pub material_code: Option<String>,
     pub goods_name: Option<String>,
+    pub material_code_match: Option<String>,
+    pub goods_name_match: Option<String>,
     pub price: Option<String>,
     pub volumn_l: Option<String>,
     pub mass_g: Option<String>,
@@ -19,140 +26,346 @@ pub struct GoodsQueryParams {
     pub max_mass_g: Option<String>,
     pub min_price: Option<String>,
     pub max_price: Option<String>,
+    pub limit: Option<String>,
+    pub offset: Option<String>,
+    pub page: Option<String>,
+    pub page_size: Option<String>,
+    pub cursor: Option<String>,
+    pub sort: Option<String>,
+    pub q: Option<String>,
+    pub fuzzy: Option<String>,
+    // Repeatable: tag=organic&tag=frozen
+    #[serde(default)]
+    pub tag: Vec<String>,
+    pub tag_mode: Option<String>,
+    pub filter: Option<String>,
+    pub facets: Option<String>,
+    // Sparse fieldset: fields=goods_id,goods_name,price
+    pub fields: Option<String>,
 }
 
+// Longest accepted name, code or tag, in characters
+pub const MAX_TEXT_LENGTH: usize = 100;
+// Punctuation accepted in text values besides letters, digits and spaces
+const TEXT_PUNCTUATION: &str = ".,-_*()[]+/@#&'\"%!?:";
+
+// Most tags a single search may filter on
+pub const MAX_TAGS: usize = 20;
+
+// Match modes accepted by material_code_match and goods_name_match. The plain modes
+// ignore case; the _cs variants are case-sensitive.
+pub const MATCH_MODES: [&str; 8] = [
+    "exact",
+    "prefix",
+    "contains",
+    "suffix",
+    "exact_cs",
+    "prefix_cs",
+    "contains_cs",
+    "suffix_cs",
+];
+
+// Columns clients may sort by. Sort parameters are checked against this whitelist
+// and mapped to `GoodsColumn`, so no client-supplied name is ever written into SQL.
+// "relevance" is only available together with a q= full-text search or fuzzy=true.
+pub const SORTABLE_COLUMNS: [&str; 10] = [
+    "goods_id",
+    "material_code",
+    "goods_name",
+    "description",
+    "price",
+    "volumn_l",
+    "mass_g",
+    "mass_base",
+    "volumn_base",
+    "relevance",
+];
+
 impl GoodsQueryParams {
-    pub fn validate_and_parse(self) -> Result<GoodsSearchParams, String> {
+    // Pagination parameters alone are not search criteria
+    pub fn has_filters(&self) -> bool {
+        self.goods_id.is_some()
+            || self.material_code.is_some()
+            || self.goods_name.is_some()
+            || self.price.is_some()
+            || self.volumn_l.is_some()
+            || self.mass_g.is_some()
+            || self.min_volumn_l.is_some()
+            || self.max_volumn_l.is_some()
+            || self.min_mass_g.is_some()
+            || self.max_mass_g.is_some()
+            || self.min_price.is_some()
+            || self.max_price.is_some()
+            || self.q.is_some()
+            || !self.tag.is_empty()
+            || self.filter.is_some()
+    }
+
+    pub fn validate_and_parse(self) -> Result<GoodsSearchParams, AppError> {
         let mut search_params = GoodsSearchParams::new();
+        // Every problem is collected so clients can report all invalid parameters at once
+        let mut errors = FieldErrors::default();
 
         // Validate and parse goods_id
         if let Some(goods_id_str) = self.goods_id {
-            if !Self::is_safe_integer(&goods_id_str) {
-                return Err("Invalid goods_id format - contains unsafe characters".to_string());
-            }
-            search_params.goods_id = Some(
-                goods_id_str
-                    .parse::<i32>()
-                    .map_err(|_| "Invalid integer format for goods_id".to_string())?,
-            );
+            search_params.goods_id = errors.capture("goods_id", "invalid_format", &goods_id_str, parse_goods_id(&goods_id_str));
         }
 
         // Validate and parse material_code
         if let Some(material_code) = self.material_code {
-            if !Self::is_safe_string(&material_code) {
-                return Err("Invalid material_code - contains unsafe characters".to_string());
+            if Self::is_valid_text(&material_code) {
+                search_params.material_code = Some(material_code);
+            } else {
+                errors.push("material_code", "invalid_characters", invalid_text_message("material_code").to_string(), &material_code);
             }
-            search_params.material_code = Some(material_code);
         }
 
         // Validate and parse goods_name
         if let Some(goods_name) = self.goods_name {
-            if !Self::is_safe_string(&goods_name) {
-                return Err("Invalid goods_name - contains unsafe characters".to_string());
+            if Self::is_valid_text(&goods_name) {
+                search_params.goods_name = Some(goods_name);
+            } else {
+                errors.push("goods_name", "invalid_characters", invalid_text_message("goods_name").to_string(), &goods_name);
             }
-            search_params.goods_name = Some(goods_name);
         }
 
-        // Validate and parse price
-        if let Some(price_str) = self.price {
-            if !Self::is_safe_decimal(&price_str) {
-                return Err("Invalid price format - contains unsafe characters".to_string());
+        // Validate and parse text match modes
+        if let Some(mode) = self.material_code_match {
+            let result = parse_match_mode(&mode, "material_code_match");
+            if let Some(text_match) = errors.capture("material_code_match", "invalid_value", &mode, result) {
+                search_params.material_code_match = text_match;
+            }
+        }
+        if let Some(mode) = self.goods_name_match {
+            let result = parse_match_mode(&mode, "goods_name_match");
+            if let Some(text_match) = errors.capture("goods_name_match", "invalid_value", &mode, result) {
+                search_params.goods_name_match = text_match;
             }
-            search_params.price = Some(
-                rust_decimal::Decimal::from_str(&price_str)
-                    .map_err(|_| "Invalid decimal format for price".to_string())?,
-            );
         }
 
-        // Validate and parse volumn_l
-        if let Some(volumn_l_str) = self.volumn_l {
-            if !Self::is_safe_decimal(&volumn_l_str) {
-                return Err("Invalid volumn_l format - contains unsafe characters".to_string());
+        // Validate and parse decimal filters
+        search_params.price = Self::parse_decimal(&mut errors, "price", self.price);
+        search_params.volumn_l = Self::parse_decimal(&mut errors, "volumn_l", self.volumn_l);
+        search_params.mass_g = Self::parse_decimal(&mut errors, "mass_g", self.mass_g);
+        search_params.min_volumn_l = Self::parse_decimal(&mut errors, "min_volumn_l", self.min_volumn_l);
+        search_params.max_volumn_l = Self::parse_decimal(&mut errors, "max_volumn_l", self.max_volumn_l);
+        search_params.min_mass_g = Self::parse_decimal(&mut errors, "min_mass_g", self.min_mass_g);
+        search_params.max_mass_g = Self::parse_decimal(&mut errors, "max_mass_g", self.max_mass_g);
+        search_params.min_price = Self::parse_decimal(&mut errors, "min_price", self.min_price);
+        search_params.max_price = Self::parse_decimal(&mut errors, "max_price", self.max_price);
+
+        // Validate full-text query
+        if let Some(q) = self.q {
+            if Self::is_valid_text(&q) && !q.trim().is_empty() {
+                search_params.text_query = Some(q);
+            } else {
+                errors.push("q", "invalid_characters", invalid_text_message("q").to_string(), &q);
             }
-            search_params.volumn_l = Some(
-                rust_decimal::Decimal::from_str(&volumn_l_str)
-                    .map_err(|_| "Invalid decimal format for volumn_l".to_string())?,
+        }
+
+        // Validate description tags
+        let tag_count = self.tag.len();
+        if tag_count > MAX_TAGS {
+            errors.push(
+                "tag",
+                "out_of_range",
+                format!("At most {} tags can be combined", MAX_TAGS),
+                &tag_count.to_string(),
             );
+        } else {
+            for tag in self.tag {
+                if Self::is_valid_text(&tag) {
+                    search_params.tags.push(tag);
+                } else {
+                    errors.push("tag", "invalid_characters", invalid_text_message("tag").to_string(), &tag);
+                }
+            }
+        }
+        if let Some(mode) = self.tag_mode {
+            if tag_count == 0 {
+                errors.push("tag_mode", "invalid_value", "tag_mode requires at least one tag", &mode);
+            } else if let Some(tag_mode) = errors.capture("tag_mode", "invalid_value", &mode, parse_tag_mode(&mode)) {
+                search_params.tag_mode = tag_mode;
+            }
+        }
+
+        // Parse the boolean filter expression
+        if let Some(filter) = self.filter {
+            search_params.filter = errors.capture("filter", "invalid_format", &filter, parse_filter(&filter));
         }
 
-        // Validate and parse mass_g
-        if let Some(mass_g_str) = self.mass_g {
-            if !Self::is_safe_decimal(&mass_g_str) {
-                return Err("Invalid mass_g format - contains unsafe characters".to_string());
+        // Validate requested facets
+        if let Some(facets) = self.facets {
+            let result = parse_facets(&facets);
+            if let Some(facets) = errors.capture("facets", "invalid_value", &facets, result) {
+                search_params.facets = facets;
             }
-            search_params.mass_g = Some(
-                rust_decimal::Decimal::from_str(&mass_g_str)
-                    .map_err(|_| "Invalid decimal format for mass_g".to_string())?,
-            );
         }
 
-        // Validate and parse min_volumn_l
-        if let Some(min_volumn_l_str) = self.min_volumn_l {
-            if !Self::is_safe_decimal(&min_volumn_l_str) {
-                return Err("Invalid min_volumn_l format - contains unsafe characters".to_string());
+        // Validate the sparse fieldset
+        if let Some(fields) = self.fields {
+            let result = parse_fields(&fields);
+            if let Some(fields) = errors.capture("fields", "invalid_value", &fields, result) {
+                search_params.fields = fields;
             }
-            search_params.min_volumn_l = Some(
-                rust_decimal::Decimal::from_str(&min_volumn_l_str)
-                    .map_err(|_| "Invalid decimal format for min_volumn_l".to_string())?,
-            );
         }
 
-        // Validate and parse max_volumn_l
-        if let Some(max_volumn_l_str) = self.max_volumn_l {
-            if !Self::is_safe_decimal(&max_volumn_l_str) {
-                return Err("Invalid max_volumn_l format - contains unsafe characters".to_string());
+        // Cross-field checks: a range whose minimum exceeds its maximum can never match
+        for (min_field, min, max_field, max) in [
+            ("min_price", search_params.min_price, "max_price", search_params.max_price),
+            ("min_mass_g", search_params.min_mass_g, "max_mass_g", search_params.max_mass_g),
+            ("min_volumn_l", search_params.min_volumn_l, "max_volumn_l", search_params.max_volumn_l),
+        ] {
+            if let (Some(min), Some(max)) = (min, max)
+                && min > max
+            {
+                errors.push(
+                    min_field,
+                    "invalid_range",
+                    format!("{} must not be greater than {} ({})", min_field, max_field, max),
+                    &min.to_string(),
+                );
             }
-            search_params.max_volumn_l = Some(
-                rust_decimal::Decimal::from_str(&max_volumn_l_str)
-                    .map_err(|_| "Invalid decimal format for max_volumn_l".to_string())?,
-            );
         }
 
-        // Validate and parse min_mass_g
-        if let Some(min_mass_g_str) = self.min_mass_g {
-            if !Self::is_safe_decimal(&min_mass_g_str) {
-                return Err("Invalid min_mass_g format - contains unsafe characters".to_string());
+        // Validate and parse pagination (limit/offset or page/page_size)
+        let (limit, offset) = Self::parse_pagination(&mut errors, self.limit, self.offset, self.page, self.page_size);
+        search_params.limit = limit;
+        search_params.offset = offset;
+
+        // Validate and parse fuzzy matching mode
+        if let Some(fuzzy) = self.fuzzy {
+            let result = parse_fuzzy_mode(&fuzzy);
+            if let Some(mode) = errors.capture("fuzzy", "invalid_value", &fuzzy, result) {
+                search_params.fuzzy = mode;
             }
-            search_params.min_mass_g = Some(
-                rust_decimal::Decimal::from_str(&min_mass_g_str)
-                    .map_err(|_| "Invalid decimal format for min_mass_g".to_string())?,
-            );
         }
 
-        // Validate and parse max_mass_g
-        if let Some(max_mass_g_str) = self.max_mass_g {
-            if !Self::is_safe_decimal(&max_mass_g_str) {
-                return Err("Invalid max_mass_g format - contains unsafe characters".to_string());
+        // Validate and parse sort; without one, full-text and fuzzy searches list the
+        // best matches first (see GoodsSearchParams::resolved_sort)
+        let mut sort_valid = true;
+        if let Some(sort) = self.sort {
+            match parse_sort(&sort) {
+                Ok(keys) if search_params.text_query.is_none()
+                    && !search_params.is_fuzzy()
+                    && keys.iter().any(|key| key.column == GoodsColumn::Relevance) =>
+                {
+                    sort_valid = false;
+                    errors.push("sort", "invalid_value", "Sorting by relevance requires a q= search or fuzzy=true", &sort);
+                }
+                Ok(keys) => search_params.sort = keys,
+                Err(e) => {
+                    sort_valid = false;
+                    errors.push("sort", "invalid_value", e.to_string(), &sort);
+                }
             }
-            search_params.max_mass_g = Some(
-                rust_decimal::Decimal::from_str(&max_mass_g_str)
-                    .map_err(|_| "Invalid decimal format for max_mass_g".to_string())?,
-            );
         }
 
-        // Validate and parse min_price
-        if let Some(min_price_str) = self.min_price {
-            if !Self::is_safe_decimal(&min_price_str) {
-                return Err("Invalid min_price format - contains unsafe characters".to_string());
+        // Validate and decode the keyset cursor. A cursor from a search that fell back to
+        // fuzzy matching keeps paging fuzzily.
+        if let Some(cursor) = self.cursor {
+            if offset != 0 {
+                errors.push(
+                    "cursor",
+                    "conflicting_parameters",
+                    "cursor cannot be combined with offset or page",
+                    &cursor,
+                );
+            } else if sort_valid {
+                let result = GoodsCursor::decode(&cursor).and_then(|c| {
+                    if c.fuzzy && search_params.fuzzy != FuzzyMode::Off {
+                        search_params.fuzzy = FuzzyMode::Always;
+                    }
+                    c.into_keyset(&search_params.resolved_sort())
+                });
+                search_params.after = errors.capture("cursor", "invalid_cursor", &cursor, result);
             }
-            search_params.min_price = Some(
-                rust_decimal::Decimal::from_str(&min_price_str)
-                    .map_err(|_| "Invalid decimal format for min_price".to_string())?,
-            );
         }
 
-        // Validate and parse max_price
-        if let Some(max_price_str) = self.max_price {
-            if !Self::is_safe_decimal(&max_price_str) {
-                return Err("Invalid max_price format - contains unsafe characters".to_string());
+        errors.into_result(search_params)
+    }
+
+    fn parse_decimal(errors: &mut FieldErrors, field: &'static str, input: Option<String>) -> Option<Decimal> {
+        let input = input?;
+        if !Self::is_safe_decimal(&input) {
+            errors.push(field, "invalid_format", format!("Invalid {} format - expected a decimal number", field), &input);
+            return None;
+        }
+        match Decimal::from_str(&input) {
+            Ok(value) => Some(value),
+            Err(_) => {
+                errors.push(field, "out_of_range", format!("Invalid decimal format for {}", field), &input);
+                None
             }
-            search_params.max_price = Some(
-                rust_decimal::Decimal::from_str(&max_price_str)
-                    .map_err(|_| "Invalid decimal format for max_price".to_string())?,
-            );
         }
+    }
 
-        Ok(search_params)
+    fn parse_pagination(
+        errors: &mut FieldErrors,
+        limit: Option<String>,
+        offset: Option<String>,
+        page: Option<String>,
+        page_size: Option<String>,
+    ) -> (i64, i64) {
+        if (limit.is_some() || offset.is_some()) && (page.is_some() || page_size.is_some()) {
+            let field = if page.is_some() { "page" } else { "page_size" };
+            let received = page.as_deref().or(page_size.as_deref()).unwrap_or_default();
+            errors.push(field, "conflicting_parameters", "Use either limit/offset or page/page_size, not both", received);
+            return (DEFAULT_PAGE_SIZE, 0);
+        }
+
+        let size_field = if limit.is_some() { "limit" } else { "page_size" };
+        let size = match limit.or(page_size) {
+            Some(size_str) => match Self::parse_non_negative(errors, size_field, &size_str) {
+                Some(0) => {
+                    errors.push(size_field, "out_of_range", "Page size must be at least 1", &size_str);
+                    DEFAULT_PAGE_SIZE
+                }
+                // Server-enforced maximum, whatever the client asks for
+                Some(size) => size.min(MAX_PAGE_SIZE),
+                None => DEFAULT_PAGE_SIZE,
+            },
+            None => DEFAULT_PAGE_SIZE,
+        };
+
+        let offset = match (offset, page) {
+            (Some(offset_str), _) => Self::parse_non_negative(errors, "offset", &offset_str).unwrap_or(0),
+            (None, Some(page_str)) => match Self::parse_non_negative(errors, "page", &page_str) {
+                Some(0) => {
+                    errors.push("page", "out_of_range", "page starts at 1", &page_str);
+                    0
+                }
+                Some(page) => match (page - 1).checked_mul(size) {
+                    Some(offset) => offset,
+                    None => {
+                        errors.push("page", "out_of_range", "page is out of range", &page_str);
+                        0
+                    }
+                },
+                None => 0,
+            },
+            (None, None) => 0,
+        };
+
+        (size, offset)
+    }
+
+    fn parse_non_negative(errors: &mut FieldErrors, field: &'static str, input: &str) -> Option<i64> {
+        if !Self::is_safe_integer(input) {
+            errors.push(field, "invalid_format", format!("Invalid {} format - expected an integer", field), input);
+            return None;
+        }
+        match input.parse::<i64>() {
+            Ok(value) if value < 0 => {
+                errors.push(field, "out_of_range", format!("{} must not be negative", field), input);
+                None
+            }
+            Ok(value) => Some(value),
+            Err(_) => {
+                errors.push(field, "out_of_range", format!("Invalid integer format for {}", field), input);
+                None
+            }
+        }
     }
 
     // SQL injection prevention - validate input contains only safe characters for integers
@@ -164,38 +377,14 @@ impl GoodsQueryParams {
             && !input.starts_with("--")  // Prevent SQL comment injection
     }
 
-    // SQL injection prevention - validate input contains only safe characters for strings
-    fn is_safe_string(input: &str) -> bool {
-        // Explicitly reject SQL injection attempts
-        let dangerous_patterns = [
-            "select", "insert", "update", "delete", "drop", "alter", "create",
-            "union", "script", "--", "/*", "*/", "'", "\"", ";", "\\",
-            "exec", "execute", "sp_", "xp_", "declare", "cast", "convert",
-            "truncate", "grant", "revoke", "into", "from", "where", "having",
-            "order by", "group by", "information_schema", "sys.", "pg_",
-            "waitfor", "delay", "sleep", "benchmark", "extractvalue",
-            "updatexml", "ascii", "char(", "chr(", "substring", "mid(",
-            "concat", "||", "&&", "|", "&", "^", "~", "<<", ">>",
-            "0x", "null", "true", "false", "if(", "case when", "else",
-            "then", "end", "exists", "not exists", "in(", "not in",
-            "between", "like", "regexp", "rlike", "sounds like"
-        ];
-        
-        let input_lower = input.to_lowercase();
-        
-        // Check for dangerous patterns
-        for pattern in &dangerous_patterns {
-            if input_lower.contains(pattern) {
-                return false;
-            }
-        }
-        
-        // Additional character validation - only allow safe characters
-        input.chars().all(|c| {
-            c.is_alphanumeric() 
-            || c.is_whitespace() 
-            || matches!(c, '.' | '-' | '_' | '*' | '(' | ')' | '[' | ']' | '+' | '/' | '@' | '#')
-        })
+    // Text validation for names, codes and tags. Every query binds text as a parameter,
+    // so this only restricts input to what real catalog values look like: letters,
+    // digits, spaces and common punctuation, between 1 and MAX_TEXT_LENGTH characters.
+    fn is_valid_text(input: &str) -> bool {
+        let length = input.chars().count();
+
+        (1..=MAX_TEXT_LENGTH).contains(&length)
+            && input.chars().all(|c| c.is_alphanumeric() || c == ' ' || TEXT_PUNCTUATION.contains(c))
     }
 
     // SQL injection prevention - validate input contains only safe characters for decimals
@@ -211,14 +400,158 @@ impl GoodsQueryParams {
     }
 }
 
-// Helper function to extract query parameters safely
-pub fn extract_query_params(query: Query<HashMap<String, String>>) -> GoodsQueryParams {
-    let params = query.0;
-    
-    GoodsQueryParams {
+// Collects every problem found while validating a request instead of stopping at the first
+#[derive(Debug, Default)]
+struct FieldErrors(Vec<FieldError>);
+
+impl FieldErrors {
+    fn push(&mut self, field: &str, code: &str, message: impl Into<String>, received_value: &str) {
+        self.0.push(FieldError {
+            field: field.to_string(),
+            code: code.to_string(),
+            message: message.into(),
+            received_value: Some(received_value.to_string()),
+        });
+    }
+
+    fn missing(&mut self, field: &str, message: impl Into<String>) {
+        self.0.push(FieldError {
+            field: field.to_string(),
+            code: "required".to_string(),
+            message: message.into(),
+            received_value: None,
+        });
+    }
+
+    // Record a failed parse under `field`, or pass the parsed value through
+    fn capture<T>(&mut self, field: &str, code: &str, received_value: &str, result: Result<T, AppError>) -> Option<T> {
+        match result {
+            Ok(value) => Some(value),
+            Err(e) => {
+                self.push(field, code, e.to_string(), received_value);
+                None
+            }
+        }
+    }
+
+    fn into_result<T>(self, value: T) -> Result<T, AppError> {
+        if self.0.is_empty() {
+            Ok(value)
+        } else {
+            Err(AppError::InvalidFields(self.0))
+        }
+    }
+}
+
+// Query parameters that filter goods, accepted by every endpoint that searches them
+pub const FILTER_QUERY_PARAMS: [&str; 19] = [
+    "goods_id",
+    "material_code",
+    "goods_name",
+    "material_code_match",
+    "goods_name_match",
+    "price",
+    "volumn_l",
+    "mass_g",
+    "min_volumn_l",
+    "max_volumn_l",
+    "min_mass_g",
+    "max_mass_g",
+    "min_price",
+    "max_price",
+    "q",
+    "fuzzy",
+    "tag",
+    "tag_mode",
+    "filter",
+];
+
+// Every query parameter GET /goods understands
+pub const GOODS_QUERY_PARAMS: [&str; 28] = [
+    "goods_id",
+    "material_code",
+    "goods_name",
+    "material_code_match",
+    "goods_name_match",
+    "price",
+    "volumn_l",
+    "mass_g",
+    "min_volumn_l",
+    "max_volumn_l",
+    "min_mass_g",
+    "max_mass_g",
+    "min_price",
+    "max_price",
+    "limit",
+    "offset",
+    "page",
+    "page_size",
+    "cursor",
+    "sort",
+    "q",
+    "fuzzy",
+    "tag",
+    "tag_mode",
+    "filter",
+    "facets",
+    "fields",
+    "format",
+];
+
+// Every query parameter GET /goods/export understands: the filters, sort and fields of
+// GET /goods, without pagination
+pub const EXPORT_QUERY_PARAMS: [&str; 22] = [
+    "goods_id",
+    "material_code",
+    "goods_name",
+    "material_code_match",
+    "goods_name_match",
+    "price",
+    "volumn_l",
+    "mass_g",
+    "min_volumn_l",
+    "max_volumn_l",
+    "min_mass_g",
+    "max_mass_g",
+    "min_price",
+    "max_price",
+    "q",
+    "fuzzy",
+    "tag",
+    "tag_mode",
+    "filter",
+    "sort",
+    "fields",
+    "format",
+];
+
+// Helper function to extract query parameters safely. In strict mode parameters not in
+// `valid` are rejected, so a misspelled filter cannot silently widen the search.
+// Every `tag` is kept; for other repeated parameters the last value wins.
+pub fn extract_query_params(
+    query: Query<Vec<(String, String)>>,
+    strict: bool,
+    valid: &[&str],
+) -> Result<GoodsQueryParams, AppError> {
+    let mut tags = Vec::new();
+    let mut params = HashMap::new();
+    for (key, value) in query.0 {
+        if key == "tag" {
+            tags.push(value.clone());
+        }
+        params.insert(key, value);
+    }
+
+    if strict {
+        check_known_params(&params, valid)?;
+    }
+
+    Ok(GoodsQueryParams {
         goods_id: params.get("goods_id").cloned(),
         material_code: params.get("material_code").cloned(),
         goods_name: params.get("goods_name").cloned(),
+        material_code_match: params.get("material_code_match").cloned(),
+        goods_name_match: params.get("goods_name_match").cloned(),
         price: params.get("price").cloned(),
         volumn_l: params.get("volumn_l").cloned(),
         mass_g: params.get("mass_g").cloned(),
@@ -228,6 +561,995 @@ pub fn extract_query_params(query: Query<HashMap<String, String>>) -> GoodsQuery
         max_mass_g: params.get("max_mass_g").cloned(),
         min_price: params.get("min_price").cloned(),
         max_price: params.get("max_price").cloned(),
+        limit: params.get("limit").cloned(),
+        offset: params.get("offset").cloned(),
+        page: params.get("page").cloned(),
+        page_size: params.get("page_size").cloned(),
+        cursor: params.get("cursor").cloned(),
+        sort: params.get("sort").cloned(),
+        q: params.get("q").cloned(),
+        fuzzy: params.get("fuzzy").cloned(),
+        tag: tags,
+        tag_mode: params.get("tag_mode").cloned(),
+        filter: params.get("filter").cloned(),
+        facets: params.get("facets").cloned(),
+        fields: params.get("fields").cloned(),
+    })
+}
+
+// Body of POST /goods/search: the GET /goods parameters as a typed JSON document. It is
+// turned back into those query parameters, so both searches validate and match alike.
+// `F` holds the filters, letting v2 spell them its own way.
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields, bound(deserialize = "F: Deserialize<'de> + Default"))]
+pub struct GoodsSearchRequest<F = SearchFilters> {
+    #[serde(default)]
+    pub filters: F,
+    // Sort keys in priority order, e.g. ["-price", "goods_name"]
+    #[serde(default)]
+    pub sort: Vec<String>,
+    #[serde(default)]
+    pub pagination: SearchPagination,
+    #[serde(default)]
+    pub facets: Vec<String>,
+    // Sparse fieldset; every field when empty
+    #[serde(default)]
+    pub fields: Vec<String>,
+}
+
+// Filters of a search document, named like the GET /goods query parameters
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct SearchFilters {
+    pub goods_id: Option<i64>,
+    pub material_code: Option<String>,
+    pub goods_name: Option<String>,
+    pub material_code_match: Option<String>,
+    pub goods_name_match: Option<String>,
+    pub price: Option<Decimal>,
+    pub volumn_l: Option<Decimal>,
+    pub mass_g: Option<Decimal>,
+    pub min_volumn_l: Option<Decimal>,
+    pub max_volumn_l: Option<Decimal>,
+    pub min_mass_g: Option<Decimal>,
+    pub max_mass_g: Option<Decimal>,
+    pub min_price: Option<Decimal>,
+    pub max_price: Option<Decimal>,
+    pub q: Option<String>,
+    pub fuzzy: Option<FuzzyFlag>,
+    #[serde(default)]
+    pub tag: Vec<String>,
+    pub tag_mode: Option<String>,
+    pub filter: Option<String>,
+}
+
+// fuzzy as a JSON boolean or as one of the fuzzy= values ("auto", "true", "false")
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum FuzzyFlag {
+    Enabled(bool),
+    Mode(String),
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct SearchPagination {
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+    pub page: Option<i64>,
+    pub page_size: Option<i64>,
+    pub cursor: Option<String>,
+}
+
+impl<F: Serialize> GoodsSearchRequest<F> {
+    // The equivalent GET /goods query parameters
+    pub fn into_query_pairs(self) -> Vec<(String, String)> {
+        let mut pairs = Vec::new();
+        for section in [serde_json::to_value(&self.filters), serde_json::to_value(&self.pagination)] {
+            if let Ok(serde_json::Value::Object(values)) = section {
+                for (key, value) in values {
+                    push_query_pair(&mut pairs, &key, value);
+                }
+            }
+        }
+        if !self.sort.is_empty() {
+            pairs.push(("sort".to_string(), self.sort.join(",")));
+        }
+        if !self.facets.is_empty() {
+            pairs.push(("facets".to_string(), self.facets.join(",")));
+        }
+        if !self.fields.is_empty() {
+            pairs.push(("fields".to_string(), self.fields.join(",")));
+        }
+        pairs
+    }
+}
+
+// Write a JSON value the way it would appear in a query string: arrays repeat the key
+// and absent values are left out
+fn push_query_pair(pairs: &mut Vec<(String, String)>, key: &str, value: serde_json::Value) {
+    match value {
+        serde_json::Value::Null => {}
+        serde_json::Value::String(text) => pairs.push((key.to_string(), text)),
+        serde_json::Value::Array(items) => {
+            for item in items {
+                push_query_pair(pairs, key, item);
+            }
+        }
+        other => pairs.push((key.to_string(), other.to_string())),
+    }
+}
+
+// Shortest prefix GET /goods/suggest answers, so a single keystroke never scans the catalog
+pub const MIN_SUGGEST_PREFIX: usize = 2;
+// Every query parameter GET /goods/suggest understands
+pub const SUGGEST_QUERY_PARAMS: [&str; 2] = ["prefix", "limit"];
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct SuggestQueryParams {
+    pub prefix: Option<String>,
+    pub limit: Option<String>,
+}
+
+impl SuggestQueryParams {
+    pub fn from_query(params: HashMap<String, String>, strict: bool) -> Result<Self, AppError> {
+        if strict {
+            check_known_params(&params, &SUGGEST_QUERY_PARAMS)?;
+        }
+        Ok(Self {
+            prefix: params.get("prefix").cloned(),
+            limit: params.get("limit").cloned(),
+        })
+    }
+
+    // The trimmed prefix and how many suggestions to return, capped at `max_limit`
+    pub fn validate(self, default_limit: i64, max_limit: i64) -> Result<(String, i64), AppError> {
+        let mut errors = FieldErrors::default();
+
+        let prefix = match self.prefix {
+            Some(prefix) if !GoodsQueryParams::is_valid_text(prefix.trim()) => {
+                errors.push("prefix", "invalid_characters", invalid_text_message("prefix").to_string(), &prefix);
+                String::new()
+            }
+            Some(prefix) if prefix.trim().chars().count() < MIN_SUGGEST_PREFIX => {
+                errors.push(
+                    "prefix",
+                    "invalid_value",
+                    format!("prefix must be at least {} characters", MIN_SUGGEST_PREFIX),
+                    &prefix,
+                );
+                String::new()
+            }
+            Some(prefix) => prefix.trim().to_string(),
+            None => {
+                errors.missing("prefix", "prefix is required");
+                String::new()
+            }
+        };
+
+        let limit = match self.limit {
+            Some(limit_str) => match GoodsQueryParams::parse_non_negative(&mut errors, "limit", &limit_str) {
+                Some(0) => {
+                    errors.push("limit", "out_of_range", "limit must be at least 1", &limit_str);
+                    default_limit
+                }
+                Some(limit) => limit.min(max_limit),
+                None => default_limit,
+            },
+            None => default_limit,
+        };
+
+        errors.into_result((prefix, limit))
+    }
+}
+
+// Reject every parameter not in `valid`, suggesting the closest valid name for each
+pub fn check_known_params(params: &HashMap<String, String>, valid: &[&str]) -> Result<(), AppError> {
+    let mut errors = FieldErrors::default();
+    let mut unknown: Vec<&String> = params
+        .keys()
+        .filter(|key| !valid.contains(&key.as_str()))
+        .collect();
+    unknown.sort();
+    for key in unknown {
+        errors.push(key, "unknown_parameter", unknown_parameter_message(key, valid), &params[key]);
+    }
+    errors.into_result(())
+}
+
+fn unknown_parameter_message(key: &str, valid: &[&str]) -> String {
+    let suggestion = closest_match(key, valid)
+        .map(|name| format!(" Did you mean '{}'?", name))
+        .unwrap_or_default();
+    format!(
+        "Unknown query parameter '{}'.{} Valid parameters: {}",
+        key,
+        suggestion,
+        valid.join(", ")
+    )
+}
+
+// Closest valid name by edit distance, if it is close enough to be a plausible typo
+pub fn closest_match<'a>(input: &str, candidates: &[&'a str]) -> Option<&'a str> {
+    let input = input.to_lowercase();
+    let max_distance = (input.chars().count() / 3).max(2);
+
+    candidates
+        .iter()
+        .map(|candidate| (edit_distance(&input, candidate), *candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+// Levenshtein distance between two strings
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+// Upper bound on identifiers resolved by one batch lookup
+pub const MAX_BATCH_SIZE: usize = 1000;
+
+// Body of POST /goods/batch
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct GoodsBatchRequest {
+    #[serde(default)]
+    pub goods_ids: Vec<i32>,
+    #[serde(default)]
+    pub material_codes: Vec<String>,
+}
+
+impl GoodsBatchRequest {
+    pub fn validate(mut self) -> Result<Self, AppError> {
+        if self.goods_ids.is_empty() && self.material_codes.is_empty() {
+            return Err(AppError::validation("Provide goods_ids and/or material_codes"));
+        }
+        if self.goods_ids.len() + self.material_codes.len() > MAX_BATCH_SIZE {
+            return Err(AppError::validation(format!("A batch may contain at most {} identifiers", MAX_BATCH_SIZE)));
+        }
+        for material_code in &self.material_codes {
+            parse_material_code(material_code)?;
+        }
+
+        // Duplicates would only be reported twice
+        self.goods_ids.sort_unstable();
+        self.goods_ids.dedup();
+        let mut seen = std::collections::HashSet::new();
+        self.material_codes.retain(|code| seen.insert(code.clone()));
+
+        Ok(self)
+    }
+}
+
+// Body of POST /goods and PUT /goods/{goods_id}
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct GoodWriteRequest {
+    pub material_code: String,
+    pub goods_name: String,
+    #[serde(default)]
+    pub description: Option<Vec<String>>,
+    pub price: Decimal,
+    pub volumn_l: Decimal,
+    pub mass_g: Decimal,
+    pub mass_base: i16,
+    pub volumn_base: i16,
+}
+
+impl GoodWriteRequest {
+    pub fn validate(self) -> Result<NewGood, AppError> {
+        validate_text_field("material_code", &self.material_code)?;
+        validate_text_field("goods_name", &self.goods_name)?;
+        if let Some(description) = &self.description {
+            validate_description(description)?;
+        }
+        validate_non_negative("price", self.price)?;
+        validate_non_negative("volumn_l", self.volumn_l)?;
+        validate_non_negative("mass_g", self.mass_g)?;
+        validate_non_negative("mass_base", self.mass_base.into())?;
+        validate_non_negative("volumn_base", self.volumn_base.into())?;
+
+        Ok(NewGood {
+            material_code: self.material_code,
+            goods_name: self.goods_name,
+            description: self.description,
+            price: self.price,
+            volumn_l: self.volumn_l,
+            mass_g: self.mass_g,
+            mass_base: self.mass_base,
+            volumn_base: self.volumn_base,
+        })
+    }
+}
+
+// Body of PATCH /goods/{goods_id}: every field is optional, `"description": null` clears it
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct GoodPatchRequest {
+    pub material_code: Option<String>,
+    pub goods_name: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_present")]
+    pub description: Option<Option<Vec<String>>>,
+    pub price: Option<Decimal>,
+    pub volumn_l: Option<Decimal>,
+    pub mass_g: Option<Decimal>,
+    pub mass_base: Option<i16>,
+    pub volumn_base: Option<i16>,
+}
+
+impl GoodPatchRequest {
+    pub fn validate(self) -> Result<GoodPatch, AppError> {
+        if let Some(material_code) = &self.material_code {
+            validate_text_field("material_code", material_code)?;
+        }
+        if let Some(goods_name) = &self.goods_name {
+            validate_text_field("goods_name", goods_name)?;
+        }
+        if let Some(Some(description)) = &self.description {
+            validate_description(description)?;
+        }
+        for (field, value) in [
+            ("price", self.price),
+            ("volumn_l", self.volumn_l),
+            ("mass_g", self.mass_g),
+            ("mass_base", self.mass_base.map(Decimal::from)),
+            ("volumn_base", self.volumn_base.map(Decimal::from)),
+        ] {
+            if let Some(value) = value {
+                validate_non_negative(field, value)?;
+            }
+        }
+
+        let patch = GoodPatch {
+            material_code: self.material_code,
+            goods_name: self.goods_name,
+            description: self.description,
+            price: self.price,
+            volumn_l: self.volumn_l,
+            mass_g: self.mass_g,
+            mass_base: self.mass_base,
+            volumn_base: self.volumn_base,
+        };
+        if patch.is_empty() {
+            return Err(AppError::validation("Patch must set at least one field"));
+        }
+
+        Ok(patch)
+    }
+}
+
+// Distinguish a missing field (None) from an explicit null (Some(None))
+pub fn deserialize_present<'de, T, D>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
+
+fn validate_text_field(field: &str, value: &str) -> Result<(), AppError> {
+    if value.trim().is_empty() {
+        return Err(AppError::validation(format!("{} must not be empty", field)));
+    }
+    if !GoodsQueryParams::is_valid_text(value) {
+        return Err(invalid_text_message(field));
+    }
+    Ok(())
+}
+
+fn invalid_text_message(field: &str) -> AppError {
+    AppError::validation(format!(
+        "Invalid {} - use 1 to {} letters, digits, spaces or the punctuation {}",
+        field, MAX_TEXT_LENGTH, TEXT_PUNCTUATION
+    ))
+}
+
+fn validate_description(description: &[String]) -> Result<(), AppError> {
+    for tag in description {
+        validate_text_field("description", tag)?;
+    }
+    Ok(())
+}
+
+fn validate_non_negative(field: &str, value: Decimal) -> Result<(), AppError> {
+    if value.is_sign_negative() && !value.is_zero() {
+        return Err(AppError::validation(format!("{} must not be negative", field)));
+    }
+    Ok(())
+}
+
+// Validate and parse a goods_id taken from a path segment
+pub fn parse_goods_id(input: &str) -> Result<i32, AppError> {
+    if !GoodsQueryParams::is_safe_integer(input) {
+        return Err(AppError::validation("Invalid goods_id format - contains unsafe characters"));
+    }
+    input
+        .parse::<i32>()
+        .map_err(|_| AppError::validation("Invalid integer format for goods_id"))
+}
+
+// Validate a material_code taken from a path segment
+pub fn parse_material_code(input: &str) -> Result<String, AppError> {
+    if !GoodsQueryParams::is_valid_text(input) {
+        return Err(invalid_text_message("material_code"));
+    }
+    Ok(input.to_string())
+}
+
+pub fn parse_match_mode(input: &str, field: &str) -> Result<TextMatch, AppError> {
+    let (name, case_sensitive) = match input.strip_suffix("_cs") {
+        Some(name) => (name, true),
+        None => (input, false),
+    };
+    let mode = match name {
+        "exact" => MatchMode::Exact,
+        "prefix" => MatchMode::Prefix,
+        "contains" => MatchMode::Contains,
+        "suffix" => MatchMode::Suffix,
+        _ => {
+            return Err(AppError::validation(format!(
+                "Invalid {} '{}'. Valid modes: {}",
+                field,
+                input,
+                MATCH_MODES.join(", ")
+            )))
+        }
+    };
+
+    Ok(TextMatch { mode, case_sensitive })
+}
+
+pub fn parse_tag_mode(input: &str) -> Result<TagMode, AppError> {
+    match input {
+        "all" => Ok(TagMode::All),
+        "any" => Ok(TagMode::Any),
+        "none" => Ok(TagMode::None),
+        _ => Err(AppError::validation(format!(
+            "Invalid tag_mode '{}'. Valid modes: all, any, none",
+            input
+        ))),
+    }
+}
+
+// fuzzy=auto (the default) retries by similarity when nothing matches exactly,
+// fuzzy=true always matches by similarity and fuzzy=false never does
+pub fn parse_fuzzy_mode(input: &str) -> Result<FuzzyMode, AppError> {
+    match input {
+        "auto" => Ok(FuzzyMode::Auto),
+        "true" => Ok(FuzzyMode::Always),
+        "false" => Ok(FuzzyMode::Off),
+        _ => Err(AppError::validation(format!(
+            "Invalid fuzzy '{}'. Valid values: auto, true, false",
+            input
+        ))),
+    }
+}
+
+// Parse a sort specification such as "-price,goods_name": comma separated column
+// names, each optionally prefixed with '-' for descending or '+' for ascending order
+pub fn parse_sort(input: &str) -> Result<Vec<SortKey>, AppError> {
+    let mut keys: Vec<SortKey> = Vec::new();
+
+    for part in input.split(',') {
+        let part = part.trim();
+        let (name, descending) = match part.strip_prefix('-') {
+            Some(name) => (name, true),
+            None => (part.strip_prefix('+').unwrap_or(part), false),
+        };
+
+        if name.is_empty() {
+            return Err(AppError::validation("sort contains an empty column name"));
+        }
+        let column = SORTABLE_COLUMNS
+            .contains(&name)
+            .then(|| match name {
+                "relevance" => Some(GoodsColumn::Relevance),
+                _ => GoodsColumn::from_name(name),
+            })
+            .flatten()
+            .ok_or_else(|| {
+                AppError::validation(format!(
+                    "Cannot sort by '{}'. Sortable columns: {}",
+                    name,
+                    SORTABLE_COLUMNS.join(", ")
+                ))
+            })?;
+        if keys.iter().any(|key| key.column == column) {
+            return Err(AppError::validation(format!("sort lists '{}' more than once", name)));
+        }
+
+        keys.push(SortKey { column, descending });
+    }
+
+    Ok(keys)
+}
+
+// Columns filter= expressions may test. Like sort columns they are checked against this
+// whitelist and mapped to `GoodsColumn`.
+pub const FILTERABLE_COLUMNS: [&str; 8] = [
+    "goods_id",
+    "material_code",
+    "goods_name",
+    "price",
+    "volumn_l",
+    "mass_g",
+    "mass_base",
+    "volumn_base",
+];
+
+// Operators of filter= expressions besides `in`: comparisons, plus contains (co),
+// starts with (sw) and ends with (ew) for text columns
+pub const FILTER_OPERATORS: [&str; 9] = ["eq", "ne", "lt", "le", "gt", "ge", "co", "sw", "ew"];
+
+// Longest accepted filter= expression, in characters
+const MAX_FILTER_LENGTH: usize = 1000;
+// Most comparisons plus IN list values, and deepest nesting of parentheses and `not`, one
+// filter may use, so a single request cannot build an arbitrarily large query
+const MAX_FILTER_TERMS: usize = 100;
+const MAX_FILTER_DEPTH: usize = 16;
+
+#[derive(Debug, Clone, PartialEq)]
+enum FilterToken {
+    Open,
+    Close,
+    Comma,
+    // Bare word: column, operator, keyword, number or unquoted text
+    Word(String),
+    // "double" or 'single' quoted text, with backslash escapes
+    Quoted(String),
+}
+
+impl FilterToken {
+    fn describe(&self) -> String {
+        match self {
+            FilterToken::Open => "'('".to_string(),
+            FilterToken::Close => "')'".to_string(),
+            FilterToken::Comma => "','".to_string(),
+            FilterToken::Word(word) => format!("'{}'", word),
+            FilterToken::Quoted(text) => format!("\"{}\"", text),
+        }
+    }
+}
+
+fn filter_error(position: usize, message: impl std::fmt::Display) -> AppError {
+    AppError::validation(format!("Invalid filter at position {}: {}", position, message))
+}
+
+fn is_filter_word_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '.' | '-' | '+')
+}
+
+// Split a filter expression into tokens, each with its 1-based character position
+fn tokenize_filter(input: &str) -> Result<Vec<(usize, FilterToken)>, AppError> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().enumerate().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        let position = i + 1;
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '(' => FilterToken::Open,
+            ')' => FilterToken::Close,
+            ',' => FilterToken::Comma,
+            '"' | '\'' => {
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        Some((_, '\\')) => match chars.next() {
+                            Some((_, escaped)) => text.push(escaped),
+                            None => return Err(filter_error(position, "unterminated string")),
+                        },
+                        Some((_, quote)) if quote == c => break,
+                        Some((_, other)) => text.push(other),
+                        None => return Err(filter_error(position, "unterminated string")),
+                    }
+                }
+                FilterToken::Quoted(text)
+            }
+            c if is_filter_word_char(c) => {
+                let mut word = c.to_string();
+                while let Some((_, next)) = chars.peek()
+                    && is_filter_word_char(*next)
+                {
+                    word.push(*next);
+                    chars.next();
+                }
+                FilterToken::Word(word)
+            }
+            other => return Err(filter_error(position, format!("unexpected character '{}'", other))),
+        };
+        tokens.push((position, token));
+    }
+
+    Ok(tokens)
+}
+
+// Recursive descent over the grammar, lowest precedence first:
+//   or        := and ("or" and)*
+//   and       := unary ("and" unary)*
+//   unary     := "not" unary | "(" or ")" | condition
+//   condition := column operator value | column "in" "(" value ("," value)* ")"
+struct FilterParser {
+    tokens: Vec<(usize, FilterToken)>,
+    pos: usize,
+    depth: usize,
+    terms: usize,
+    // Position reported for errors at the end of the input
+    end: usize,
+}
+
+impl FilterParser {
+    fn peek(&self) -> Option<&FilterToken> {
+        self.tokens.get(self.pos).map(|(_, token)| token)
+    }
+
+    fn position(&self) -> usize {
+        self.tokens.get(self.pos).map_or(self.end, |(position, _)| *position)
+    }
+
+    fn at_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(FilterToken::Word(word)) if word.eq_ignore_ascii_case(keyword))
+    }
+
+    fn found(&self) -> String {
+        self.peek().map_or("the end of the filter".to_string(), FilterToken::describe)
+    }
+
+    fn parse_or(&mut self) -> Result<FilterExpr, AppError> {
+        let mut expr = self.parse_and()?;
+        while self.at_keyword("or") {
+            self.pos += 1;
+            let right = self.parse_and()?;
+            expr = FilterExpr::Or(Box::new(expr), Box::new(right));
+        }
+        Ok(expr)
+    }
+
+    fn parse_and(&mut self) -> Result<FilterExpr, AppError> {
+        let mut expr = self.parse_unary()?;
+        while self.at_keyword("and") {
+            self.pos += 1;
+            let right = self.parse_unary()?;
+            expr = FilterExpr::And(Box::new(expr), Box::new(right));
+        }
+        Ok(expr)
+    }
+
+    fn parse_unary(&mut self) -> Result<FilterExpr, AppError> {
+        self.depth += 1;
+        if self.depth > MAX_FILTER_DEPTH {
+            return Err(filter_error(self.position(), format!("nested more than {} levels deep", MAX_FILTER_DEPTH)));
+        }
+
+        let expr = if self.at_keyword("not") {
+            self.pos += 1;
+            FilterExpr::Not(Box::new(self.parse_unary()?))
+        } else if self.peek() == Some(&FilterToken::Open) {
+            self.pos += 1;
+            let expr = self.parse_or()?;
+            if self.peek() != Some(&FilterToken::Close) {
+                return Err(filter_error(self.position(), format!("expected ')' but found {}", self.found())));
+            }
+            self.pos += 1;
+            expr
+        } else {
+            self.parse_condition()?
+        };
+
+        self.depth -= 1;
+        Ok(expr)
+    }
+
+    fn parse_condition(&mut self) -> Result<FilterExpr, AppError> {
+        let position = self.position();
+        let name = match self.peek() {
+            Some(FilterToken::Word(word)) => word.clone(),
+            _ => return Err(filter_error(position, format!("expected a column name but found {}", self.found()))),
+        };
+        self.pos += 1;
+        let column = FILTERABLE_COLUMNS
+            .contains(&name.as_str())
+            .then(|| GoodsColumn::from_name(&name))
+            .flatten()
+            .ok_or_else(|| {
+                let suggestion = closest_match(&name, &FILTERABLE_COLUMNS)
+                    .map(|column| format!(" Did you mean '{}'?", column))
+                    .unwrap_or_default();
+                filter_error(
+                    position,
+                    format!("cannot filter on '{}'.{} Filterable columns: {}", name, suggestion, FILTERABLE_COLUMNS.join(", ")),
+                )
+            })?;
+
+        let position = self.position();
+        let operator = match self.peek() {
+            Some(FilterToken::Word(word)) => word.to_ascii_lowercase(),
+            _ => {
+                return Err(filter_error(
+                    position,
+                    format!("expected an operator after '{}' but found {}", name, self.found()),
+                ))
+            }
+        };
+        self.pos += 1;
+
+        if operator == "in" {
+            if self.peek() != Some(&FilterToken::Open) {
+                return Err(filter_error(self.position(), format!("expected '(' after 'in' but found {}", self.found())));
+            }
+            self.pos += 1;
+            let mut values = vec![self.parse_value(column)?];
+            loop {
+                match self.peek() {
+                    Some(FilterToken::Comma) => {
+                        self.pos += 1;
+                        values.push(self.parse_value(column)?);
+                    }
+                    Some(FilterToken::Close) => {
+                        self.pos += 1;
+                        break;
+                    }
+                    _ => {
+                        return Err(filter_error(
+                            self.position(),
+                            format!("expected ',' or ')' but found {}", self.found()),
+                        ))
+                    }
+                }
+            }
+            return Ok(FilterExpr::In { column, values });
+        }
+
+        let op = parse_filter_op(&operator).ok_or_else(|| {
+            filter_error(
+                position,
+                format!("unknown operator '{}'. Operators: {}, in", operator, FILTER_OPERATORS.join(", ")),
+            )
+        })?;
+        let is_text = matches!(column.sort_value(&Good::default()), SortValue::Text(_));
+        let text_op = matches!(op, FilterOp::Eq | FilterOp::Ne | FilterOp::Contains | FilterOp::StartsWith | FilterOp::EndsWith);
+        let numeric_op = !matches!(op, FilterOp::Contains | FilterOp::StartsWith | FilterOp::EndsWith);
+        if (is_text && !text_op) || (!is_text && !numeric_op) {
+            let supported = if is_text { "eq, ne, co, sw, ew, in" } else { "eq, ne, lt, le, gt, ge, in" };
+            return Err(filter_error(
+                position,
+                format!("'{}' does not apply to {}, which supports {}", operator, name, supported),
+            ));
+        }
+
+        let value = self.parse_value(column)?;
+        Ok(FilterExpr::Compare { column, op, value })
+    }
+
+    // A literal typed for `column`: integers and decimals for numeric columns, quoted or
+    // bare text (validated like other text filters) for text columns
+    fn parse_value(&mut self, column: GoodsColumn) -> Result<SortValue, AppError> {
+        self.terms += 1;
+        if self.terms > MAX_FILTER_TERMS {
+            return Err(filter_error(
+                self.position(),
+                format!("more than {} conditions and values", MAX_FILTER_TERMS),
+            ));
+        }
+
+        let position = self.position();
+        let raw = match self.peek() {
+            Some(FilterToken::Word(word)) | Some(FilterToken::Quoted(word)) => word.clone(),
+            _ => return Err(filter_error(position, format!("expected a value but found {}", self.found()))),
+        };
+        self.pos += 1;
+
+        match column.sort_value(&Good::default()) {
+            SortValue::Integer(_) => raw
+                .parse::<i64>()
+                .map(SortValue::Integer)
+                .map_err(|_| filter_error(position, format!("{} expects an integer, got '{}'", column.name(), raw))),
+            SortValue::Decimal(_) if GoodsQueryParams::is_safe_decimal(&raw) => Decimal::from_str(&raw)
+                .map(SortValue::Decimal)
+                .map_err(|_| filter_error(position, format!("{} expects a decimal number, got '{}'", column.name(), raw))),
+            SortValue::Decimal(_) => Err(filter_error(
+                position,
+                format!("{} expects a decimal number, got '{}'", column.name(), raw),
+            )),
+            _ if GoodsQueryParams::is_valid_text(&raw) => Ok(SortValue::Text(raw)),
+            _ => Err(filter_error(position, invalid_text_message(column.name()))),
+        }
+    }
+}
+
+fn parse_filter_op(name: &str) -> Option<FilterOp> {
+    let op = match name {
+        "eq" => FilterOp::Eq,
+        "ne" => FilterOp::Ne,
+        "lt" => FilterOp::Lt,
+        "le" => FilterOp::Le,
+        "gt" => FilterOp::Gt,
+        "ge" => FilterOp::Ge,
+        "co" => FilterOp::Contains,
+        "sw" => FilterOp::StartsWith,
+        "ew" => FilterOp::EndsWith,
+        _ => return None,
+    };
+    Some(op)
+}
+
+// Parse a filter= expression such as
+//   (price lt 10 or mass_g lt 50) and not goods_name co "refill"
+//   material_code in (A100, A200, "B 300")
+// Keywords and operators ignore case; `and` binds tighter than `or`.
+pub fn parse_filter(input: &str) -> Result<FilterExpr, AppError> {
+    let length = input.chars().count();
+    if length > MAX_FILTER_LENGTH {
+        return Err(AppError::validation(format!(
+            "filter must not be longer than {} characters",
+            MAX_FILTER_LENGTH
+        )));
+    }
+
+    let tokens = tokenize_filter(input)?;
+    if tokens.is_empty() {
+        return Err(AppError::validation("filter is empty"));
+    }
+
+    let mut parser = FilterParser {
+        tokens,
+        pos: 0,
+        depth: 0,
+        terms: 0,
+        end: length + 1,
+    };
+    let expr = parser.parse_or()?;
+    if parser.pos < parser.tokens.len() {
+        return Err(filter_error(parser.position(), format!("unexpected {}", parser.found())));
+    }
+
+    Ok(expr)
+}
+
+// Parse a comma separated list of facets such as "price,tag"
+pub fn parse_facets(input: &str) -> Result<Vec<Facet>, AppError> {
+    let mut facets = Vec::new();
+    for name in input.split(',').map(str::trim) {
+        let facet = Facet::from_name(name).ok_or_else(|| {
+            AppError::validation(format!(
+                "Unknown facet '{}'. Available facets: {}",
+                name,
+                Facet::ALL.map(|facet| facet.name()).join(", ")
+            ))
+        })?;
+        if !facets.contains(&facet) {
+            facets.push(facet);
+        }
+    }
+    Ok(facets)
+}
+
+// Parse a comma separated list of response fields such as "goods_id,goods_name,price".
+// Fields are the columns of `Good` plus relevance, so they share the sortable names.
+pub fn parse_fields(input: &str) -> Result<Vec<GoodsColumn>, AppError> {
+    let mut fields = Vec::new();
+    for name in input.split(',').map(str::trim) {
+        let column = match name {
+            "relevance" => Some(GoodsColumn::Relevance),
+            _ => GoodsColumn::from_name(name),
+        }
+        .ok_or_else(|| {
+            let suggestion = closest_match(name, &SORTABLE_COLUMNS)
+                .map(|column| format!(" Did you mean '{}'?", column))
+                .unwrap_or_default();
+            AppError::validation(format!(
+                "Unknown field '{}'.{} Available fields: {}",
+                name,
+                suggestion,
+                SORTABLE_COLUMNS.join(", ")
+            ))
+        })?;
+        if !fields.contains(&column) {
+            fields.push(column);
+        }
+    }
+    Ok(fields)
+}
+
+pub fn format_sort(sort: &[SortKey]) -> String {
+    sort.iter()
+        .map(|key| format!("{}{}", if key.descending { "-" } else { "" }, key.column.name()))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+// Keyset cursor pointing just after the last good of a page. It records the sort it was
+// issued for and that row's sort key values, so it only depends on the ordering and
+// stays valid whatever filters are applied. The encoding is hex JSON and is not signed:
+// clients can read and edit it, which only moves them to another position in the same
+// ordering, since the values are type-checked against the sort and bound as parameters.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GoodsCursor {
+    #[serde(rename = "v")]
+    version: u8,
+    #[serde(rename = "s")]
+    sort: String,
+    #[serde(rename = "k")]
+    values: Vec<SortValue>,
+    #[serde(rename = "id")]
+    goods_id: i32,
+    // Issued by a fuzzy search, so later pages must match fuzzily too
+    #[serde(rename = "f", default, skip_serializing_if = "std::ops::Not::not")]
+    fuzzy: bool,
+}
+
+impl GoodsCursor {
+    const VERSION: u8 = 1;
+
+    pub fn new(sort: &[SortKey], last: &Good) -> Self {
+        Self {
+            version: Self::VERSION,
+            sort: format_sort(sort),
+            values: sort.iter().map(|key| key.column.sort_value(last)).collect(),
+            goods_id: last.goods_id,
+            fuzzy: false,
+        }
+    }
+
+    pub fn with_fuzzy(mut self, fuzzy: bool) -> Self {
+        self.fuzzy = fuzzy;
+        self
+    }
+
+    pub fn encode(&self) -> String {
+        serde_json::to_vec(self)
+            .unwrap_or_default()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+
+    pub fn decode(input: &str) -> Result<Self, AppError> {
+        let invalid = || AppError::validation("Invalid cursor");
+
+        if input.is_empty() || !input.len().is_multiple_of(2) || !input.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(invalid());
+        }
+        let bytes = (0..input.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&input[i..i + 2], 16))
+            .collect::<Result<Vec<u8>, _>>()
+            .map_err(|_| invalid())?;
+        let cursor: Self = serde_json::from_slice(&bytes).map_err(|_| invalid())?;
+        if cursor.version != Self::VERSION {
+            return Err(invalid());
+        }
+
+        Ok(cursor)
+    }
+
+    // Check the cursor belongs to the requested sort and turn it into a keyset position
+    pub fn into_keyset(self, sort: &[SortKey]) -> Result<Keyset, AppError> {
+        if self.sort != format_sort(sort) {
+            return Err(AppError::validation("cursor was issued for a different sort order"));
+        }
+        let types_match = self.values.len() == sort.len()
+            && sort.iter().zip(&self.values).all(|(key, value)| key.column.accepts(value));
+        if !types_match {
+            return Err(AppError::validation("Invalid cursor"));
+        }
+
+        Ok(Keyset {
+            values: self.values,
+            goods_id: self.goods_id,
+        })
     }
 }
 
@@ -268,19 +1590,805 @@ mod tests {
     }
 
     #[test]
-    fn test_safe_string_validation() {
+    fn test_text_validation() {
         // Valid strings
-        assert!(GoodsQueryParams::is_safe_string("iPhone"));
-        assert!(GoodsQueryParams::is_safe_string("Samsung Galaxy"));
-        assert!(GoodsQueryParams::is_safe_string("*"));
-        assert!(GoodsQueryParams::is_safe_string("APL-123"));
-        
-        // Invalid strings (SQL injection attempts)
-        assert!(!GoodsQueryParams::is_safe_string("'; DROP TABLE users; --"));
-        assert!(!GoodsQueryParams::is_safe_string("SELECT * FROM"));
-        assert!(!GoodsQueryParams::is_safe_string("UNION SELECT"));
-        assert!(!GoodsQueryParams::is_safe_string("INSERT INTO"));
-        assert!(!GoodsQueryParams::is_safe_string("/* comment */"));
-        assert!(!GoodsQueryParams::is_safe_string("exec sp_"));
-    }
-}
\ No newline at end of file
+        assert!(GoodsQueryParams::is_valid_text("iPhone"));
+        assert!(GoodsQueryParams::is_valid_text("Samsung Galaxy"));
+        assert!(GoodsQueryParams::is_valid_text("*"));
+        assert!(GoodsQueryParams::is_valid_text("APL-123"));
+
+        // Real catalog names containing SQL keywords or punctuation
+        for name in [
+            "Weekend Blend",
+            "Sleep Mask",
+            "Dairy-free & True Organic",
+            "Null Pointer T-Shirt",
+            "Friend's Blend",
+            "100% Juice",
+            "Select Reserve",
+            "Crème Brûlée",
+            "Ascii Art Mug",
+        ] {
+            assert!(GoodsQueryParams::is_valid_text(name), "{} should be valid", name);
+        }
+
+        // Invalid strings: outside the allowed character set or length
+        assert!(!GoodsQueryParams::is_valid_text(""));
+        assert!(!GoodsQueryParams::is_valid_text("'; DROP TABLE users; --"));
+        assert!(!GoodsQueryParams::is_valid_text("a\nb"));
+        assert!(!GoodsQueryParams::is_valid_text("tab\there"));
+        assert!(!GoodsQueryParams::is_valid_text("x = 1"));
+        assert!(!GoodsQueryParams::is_valid_text("<script>"));
+        assert!(!GoodsQueryParams::is_valid_text(&"a".repeat(MAX_TEXT_LENGTH + 1)));
+        assert!(GoodsQueryParams::is_valid_text(&"a".repeat(MAX_TEXT_LENGTH)));
+    }
+
+    #[test]
+    fn test_injection_strings_are_only_bound_as_parameters() {
+        // These pass the character set check, and are harmless because they never become SQL text
+        for payload in ["' OR 'a' LIKE 'a", "SELECT * FROM goods", "/* comment */", "exec sp_"] {
+            assert!(GoodsQueryParams::is_valid_text(payload), "{} should be valid", payload);
+
+            let params = GoodsQueryParams {
+                goods_name: Some(payload.to_string()),
+                material_code: Some(payload.to_string()),
+                ..Default::default()
+            };
+            let search_params = params.validate_and_parse().unwrap();
+            let sql = crate::tables::GoodsTable::search_sql(&search_params);
+            assert!(!sql.contains(payload), "{} leaked into SQL: {}", payload, sql);
+            assert!(sql.contains("goods_name ILIKE $"));
+            assert!(sql.contains("material_code ILIKE $"));
+        }
+    }
+
+    #[test]
+    fn test_pagination_defaults() {
+        let params = GoodsQueryParams {
+            goods_name: Some("*".to_string()),
+            ..Default::default()
+        };
+        let search_params = params.validate_and_parse().unwrap();
+        assert_eq!(search_params.limit, DEFAULT_PAGE_SIZE);
+        assert_eq!(search_params.offset, 0);
+    }
+
+    #[test]
+    fn test_pagination_parsing() {
+        let params = GoodsQueryParams {
+            limit: Some("20".to_string()),
+            offset: Some("40".to_string()),
+            ..Default::default()
+        };
+        let search_params = params.validate_and_parse().unwrap();
+        assert_eq!((search_params.limit, search_params.offset), (20, 40));
+
+        let params = GoodsQueryParams {
+            page: Some("3".to_string()),
+            page_size: Some("10".to_string()),
+            ..Default::default()
+        };
+        let search_params = params.validate_and_parse().unwrap();
+        assert_eq!((search_params.limit, search_params.offset), (10, 20));
+
+        // Oversized pages are capped at the server maximum
+        let params = GoodsQueryParams {
+            limit: Some("100000".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(params.validate_and_parse().unwrap().limit, MAX_PAGE_SIZE);
+    }
+
+    #[test]
+    fn test_pagination_rejects_invalid_values() {
+        let invalid = [
+            GoodsQueryParams { limit: Some("0".to_string()), ..Default::default() },
+            GoodsQueryParams { offset: Some("-1".to_string()), ..Default::default() },
+            GoodsQueryParams { page: Some("0".to_string()), ..Default::default() },
+            GoodsQueryParams { page_size: Some("abc".to_string()), ..Default::default() },
+            GoodsQueryParams {
+                limit: Some("10".to_string()),
+                page: Some("2".to_string()),
+                ..Default::default()
+            },
+        ];
+        for params in invalid {
+            assert!(params.validate_and_parse().is_err());
+        }
+    }
+
+    fn sample_good() -> Good {
+        Good {
+            goods_id: 42,
+            goods_name: "Sparkling Water".to_string(),
+            price: rust_decimal::Decimal::new(1250, 2),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_cursor_round_trip() {
+        let cursor = GoodsCursor::new(&[], &sample_good());
+        let encoded = cursor.encode();
+        assert_eq!(GoodsCursor::decode(&encoded).unwrap(), cursor);
+
+        let params = GoodsQueryParams {
+            goods_name: Some("*".to_string()),
+            cursor: Some(encoded),
+            ..Default::default()
+        };
+        let after = params.validate_and_parse().unwrap().after.unwrap();
+        assert_eq!(after.goods_id, 42);
+        assert!(after.values.is_empty());
+    }
+
+    #[test]
+    fn test_cursor_carries_sort_values() {
+        let sort = parse_sort("-price,goods_name").unwrap();
+        let encoded = GoodsCursor::new(&sort, &sample_good()).encode();
+
+        let params = GoodsQueryParams {
+            goods_name: Some("*".to_string()),
+            sort: Some("-price,goods_name".to_string()),
+            cursor: Some(encoded.clone()),
+            ..Default::default()
+        };
+        let after = params.validate_and_parse().unwrap().after.unwrap();
+        assert_eq!(
+            after.values,
+            vec![
+                SortValue::Decimal(rust_decimal::Decimal::new(1250, 2)),
+                SortValue::Text("Sparkling Water".to_string()),
+            ]
+        );
+
+        // The cursor stays bound to the sort it was issued for
+        let params = GoodsQueryParams {
+            goods_name: Some("*".to_string()),
+            sort: Some("price".to_string()),
+            cursor: Some(encoded),
+            ..Default::default()
+        };
+        assert!(params.validate_and_parse().is_err());
+    }
+
+    #[test]
+    fn test_cursor_rejects_invalid_values() {
+        assert!(GoodsCursor::decode("").is_err());
+        assert!(GoodsCursor::decode("42").is_err());
+        assert!(GoodsCursor::decode("zz").is_err());
+        assert!(GoodsCursor::decode("'; DROP TABLE goods; --").is_err());
+
+        let params = GoodsQueryParams {
+            cursor: Some(GoodsCursor::new(&[], &sample_good()).encode()),
+            offset: Some("10".to_string()),
+            ..Default::default()
+        };
+        assert!(params.validate_and_parse().is_err());
+    }
+
+    #[test]
+    fn test_sort_parsing() {
+        let sort = parse_sort("-price,goods_name").unwrap();
+        assert_eq!(
+            sort,
+            vec![
+                SortKey { column: GoodsColumn::Price, descending: true },
+                SortKey { column: GoodsColumn::GoodsName, descending: false },
+            ]
+        );
+        assert_eq!(format_sort(&sort), "-price,goods_name");
+
+        // Every column of Good is sortable
+        for column in GoodsColumn::ALL {
+            assert!(parse_sort(&format!("-{}", column.name())).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_sort_rejects_unknown_columns() {
+        assert!(parse_sort("").is_err());
+        assert!(parse_sort("price,").is_err());
+        assert!(parse_sort("weight").is_err());
+        assert!(parse_sort("price,-price").is_err());
+        assert!(parse_sort("price; DROP TABLE goods").is_err());
+        assert!(parse_sort("goods_id DESC").is_err());
+    }
+
+    #[test]
+    fn test_path_parameter_parsing() {
+        assert_eq!(parse_goods_id("5"), Ok(5));
+        assert!(parse_goods_id("5.0").is_err());
+        assert!(parse_goods_id("99999999999").is_err());
+        assert!(parse_goods_id("1 OR 1=1").is_err());
+
+        assert_eq!(parse_material_code("APL-123"), Ok("APL-123".to_string()));
+        assert!(parse_material_code("").is_err());
+        assert!(parse_material_code("A'; DROP TABLE goods; --").is_err());
+    }
+
+    #[test]
+    fn test_batch_request_validation() {
+        let request = GoodsBatchRequest {
+            goods_ids: vec![3, 1, 3],
+            material_codes: vec!["B-2".to_string(), "A-1".to_string(), "B-2".to_string()],
+        }
+        .validate()
+        .unwrap();
+        assert_eq!(request.goods_ids, vec![1, 3]);
+        assert_eq!(request.material_codes, vec!["B-2".to_string(), "A-1".to_string()]);
+
+        assert!(GoodsBatchRequest::default().validate().is_err());
+        assert!(GoodsBatchRequest {
+            goods_ids: (0..=MAX_BATCH_SIZE as i32).collect(),
+            ..Default::default()
+        }
+        .validate()
+        .is_err());
+        assert!(GoodsBatchRequest {
+            material_codes: vec!["'; DROP TABLE goods; --".to_string()],
+            ..Default::default()
+        }
+        .validate()
+        .is_err());
+    }
+
+    #[test]
+    fn test_write_request_validation() {
+        let body = r#"{
+            "material_code": "APL-123",
+            "goods_name": "Apple Juice",
+            "description": ["organic"],
+            "price": "2.50",
+            "volumn_l": "1.0",
+            "mass_g": "1050",
+            "mass_base": 1,
+            "volumn_base": 1
+        }"#;
+        let request: GoodWriteRequest = serde_json::from_str(body).unwrap();
+        let good = request.clone().validate().unwrap();
+        assert_eq!(good.goods_name, "Apple Juice");
+        assert_eq!(good.price, Decimal::new(250, 2));
+
+        let invalid = [
+            GoodWriteRequest { goods_name: " ".to_string(), ..request.clone() },
+            GoodWriteRequest { material_code: "A'; DROP TABLE goods; --".to_string(), ..request.clone() },
+            GoodWriteRequest { price: Decimal::new(-1, 0), ..request.clone() },
+            GoodWriteRequest { description: Some(vec![String::new()]), ..request.clone() },
+        ];
+        for request in invalid {
+            assert!(request.validate().is_err());
+        }
+
+        // Unknown fields such as goods_id are rejected rather than ignored
+        assert!(serde_json::from_str::<GoodWriteRequest>(r#"{"goods_id": 1}"#).is_err());
+    }
+
+    #[test]
+    fn test_patch_request_validation() {
+        let patch: GoodPatchRequest = serde_json::from_str(r#"{"price": "3.10"}"#).unwrap();
+        let patch = patch.validate().unwrap();
+        assert_eq!(patch.price, Some(Decimal::new(310, 2)));
+        assert_eq!(patch.description, None);
+
+        // An explicit null clears the description
+        let patch: GoodPatchRequest = serde_json::from_str(r#"{"description": null}"#).unwrap();
+        assert_eq!(patch.validate().unwrap().description, Some(None));
+
+        assert!(GoodPatchRequest::default().validate().is_err());
+        let patch: GoodPatchRequest = serde_json::from_str(r#"{"mass_g": "-5"}"#).unwrap();
+        assert!(patch.validate().is_err());
+    }
+
+    #[test]
+    fn test_match_mode_parsing() {
+        assert_eq!(
+            parse_match_mode("prefix", "goods_name_match"),
+            Ok(TextMatch { mode: MatchMode::Prefix, case_sensitive: false })
+        );
+        assert_eq!(
+            parse_match_mode("exact_cs", "goods_name_match"),
+            Ok(TextMatch { mode: MatchMode::Exact, case_sensitive: true })
+        );
+        for mode in MATCH_MODES {
+            assert!(parse_match_mode(mode, "goods_name_match").is_ok());
+        }
+        assert!(parse_match_mode("fuzzy", "goods_name_match").is_err());
+        assert!(parse_match_mode("_cs", "goods_name_match").is_err());
+
+        let params = GoodsQueryParams {
+            goods_name: Some("Cola".to_string()),
+            goods_name_match: Some("suffix_cs".to_string()),
+            ..Default::default()
+        };
+        let search_params = params.validate_and_parse().unwrap();
+        assert_eq!(search_params.goods_name_match.mode, MatchMode::Suffix);
+        assert_eq!(search_params.material_code_match, TextMatch::default());
+    }
+
+    #[test]
+    fn test_all_invalid_parameters_are_reported() {
+        let params = GoodsQueryParams {
+            goods_id: Some("abc".to_string()),
+            goods_name: Some("<b>".to_string()),
+            min_price: Some("50".to_string()),
+            max_price: Some("10".to_string()),
+            min_mass_g: Some("500".to_string()),
+            max_mass_g: Some("100".to_string()),
+            sort: Some("weight".to_string()),
+            limit: Some("-1".to_string()),
+            ..Default::default()
+        };
+        let errors = match params.validate_and_parse() {
+            Err(AppError::InvalidFields(errors)) => errors,
+            other => panic!("expected field errors, got {:?}", other),
+        };
+
+        let reported: Vec<(&str, &str)> = errors
+            .iter()
+            .map(|error| (error.field.as_str(), error.code.as_str()))
+            .collect();
+        assert_eq!(
+            reported,
+            vec![
+                ("goods_id", "invalid_format"),
+                ("goods_name", "invalid_characters"),
+                ("min_price", "invalid_range"),
+                ("min_mass_g", "invalid_range"),
+                ("limit", "out_of_range"),
+                ("sort", "invalid_value"),
+            ]
+        );
+        assert_eq!(errors[0].received_value.as_deref(), Some("abc"));
+        assert_eq!(errors[2].received_value.as_deref(), Some("50"));
+    }
+
+    fn query(pairs: &[(&str, &str)]) -> Query<Vec<(String, String)>> {
+        Query(
+            pairs
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_unknown_parameters_rejected_in_strict_mode() {
+        let errors = match extract_query_params(query(&[("goods_name", "*"), ("volume_l", "2")]), true, &GOODS_QUERY_PARAMS) {
+            Err(AppError::InvalidFields(errors)) => errors,
+            other => panic!("expected field errors, got {:?}", other),
+        };
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].field, "volume_l");
+        assert_eq!(errors[0].code, "unknown_parameter");
+        assert_eq!(errors[0].received_value.as_deref(), Some("2"));
+        assert!(errors[0].message.contains("Did you mean 'volumn_l'?"));
+        assert!(errors[0].message.contains("max_price"));
+
+        // Lenient mode keeps the historical behaviour of ignoring unknown keys
+        let params = extract_query_params(query(&[("goods_name", "*"), ("volume_l", "2")]), false, &GOODS_QUERY_PARAMS).unwrap();
+        assert_eq!(params.goods_name.as_deref(), Some("*"));
+
+        for name in GOODS_QUERY_PARAMS {
+            assert!(extract_query_params(query(&[(name, "1")]), true, &GOODS_QUERY_PARAMS).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_closest_match_suggestions() {
+        assert_eq!(closest_match("volume_l", &GOODS_QUERY_PARAMS), Some("volumn_l"));
+        assert_eq!(closest_match("goodsname", &GOODS_QUERY_PARAMS), Some("goods_name"));
+        assert_eq!(closest_match("MAX_PRICE", &GOODS_QUERY_PARAMS), Some("max_price"));
+        assert_eq!(closest_match("colour", &GOODS_QUERY_PARAMS), None);
+    }
+
+    #[test]
+    fn test_full_text_query_parsing() {
+        let params = GoodsQueryParams {
+            q: Some("\"green tea\" -decaf".to_string()),
+            ..Default::default()
+        };
+        let search_params = params.validate_and_parse().unwrap();
+        assert_eq!(search_params.text_query.as_deref(), Some("\"green tea\" -decaf"));
+        // Most relevant first unless the client sorts explicitly
+        assert_eq!(
+            search_params.resolved_sort(),
+            vec![SortKey { column: GoodsColumn::Relevance, descending: true }]
+        );
+
+        let sql = crate::tables::GoodsTable::search_sql(&search_params);
+        assert!(sql.contains("websearch_to_tsquery('english', $1)) AS relevance"));
+        assert!(sql.contains("ORDER BY relevance DESC, goods_id ASC"));
+
+        let params = GoodsQueryParams {
+            q: Some("tea".to_string()),
+            sort: Some("price".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(params.validate_and_parse().unwrap().sort[0].column, GoodsColumn::Price);
+
+        // Relevance only exists for full-text searches
+        let params = GoodsQueryParams {
+            goods_name: Some("*".to_string()),
+            sort: Some("-relevance".to_string()),
+            ..Default::default()
+        };
+        assert!(params.validate_and_parse().is_err());
+
+        let params = GoodsQueryParams { q: Some("  ".to_string()), ..Default::default() };
+        assert!(params.validate_and_parse().is_err());
+    }
+
+    #[test]
+    fn test_cursor_pages_by_relevance() {
+        let good = Good { relevance: Some(0.25), ..sample_good() };
+        let params = GoodsQueryParams {
+            q: Some("water".to_string()),
+            cursor: Some(GoodsCursor::new(&parse_sort("-relevance").unwrap(), &good).encode()),
+            ..Default::default()
+        };
+        let after = params.validate_and_parse().unwrap().after.unwrap();
+        assert_eq!(after.values, vec![SortValue::Float(0.25)]);
+    }
+
+    #[test]
+    fn test_fuzzy_mode_parsing() {
+        assert_eq!(GoodsQueryParams::default().validate_and_parse().unwrap().fuzzy, FuzzyMode::Auto);
+        for (input, mode) in [("auto", FuzzyMode::Auto), ("true", FuzzyMode::Always), ("false", FuzzyMode::Off)] {
+            let params = GoodsQueryParams {
+                goods_name: Some("samsng".to_string()),
+                fuzzy: Some(input.to_string()),
+                ..Default::default()
+            };
+            assert_eq!(params.validate_and_parse().unwrap().fuzzy, mode);
+        }
+
+        let params = GoodsQueryParams { fuzzy: Some("yes".to_string()), ..Default::default() };
+        match params.validate_and_parse() {
+            Err(AppError::InvalidFields(errors)) => assert_eq!(errors[0].field, "fuzzy"),
+            other => panic!("expected invalid fuzzy, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_fuzzy_search_ranks_by_similarity() {
+        let params = GoodsQueryParams {
+            goods_name: Some("samsng galxy".to_string()),
+            min_price: Some("100".to_string()),
+            fuzzy: Some("true".to_string()),
+            ..Default::default()
+        };
+        let search_params = params.validate_and_parse().unwrap();
+        assert!(search_params.is_fuzzy());
+        assert_eq!(search_params.fuzzy_terms(), vec![("samsng galxy", &["goods_name"][..])]);
+
+        // Similarity replaces the exact name match; other filters still apply
+        let sql = crate::tables::GoodsTable::search_sql(&search_params);
+        assert!(sql.contains("word_similarity($1, goods_name) AS relevance"));
+        assert!(sql.contains("word_similarity($2, goods_name) >= $3"));
+        assert!(sql.contains("price >= $4"));
+        assert!(!sql.contains("ILIKE"));
+        assert!(sql.contains("ORDER BY relevance DESC, goods_id ASC"));
+
+        // Without a text term there is nothing to match fuzzily
+        let params = GoodsQueryParams {
+            min_price: Some("100".to_string()),
+            fuzzy: Some("true".to_string()),
+            ..Default::default()
+        };
+        let search_params = params.validate_and_parse().unwrap();
+        assert!(!search_params.is_fuzzy());
+        assert!(!crate::tables::GoodsTable::search_sql(&search_params).contains("word_similarity"));
+    }
+
+    #[test]
+    fn test_fuzzy_cursor_keeps_paging_fuzzily() {
+        let good = Good { relevance: Some(0.5), ..sample_good() };
+        let sort = parse_sort("-relevance").unwrap();
+        let params = GoodsQueryParams {
+            goods_name: Some("samsng".to_string()),
+            cursor: Some(GoodsCursor::new(&sort, &good).with_fuzzy(true).encode()),
+            ..Default::default()
+        };
+        let search_params = params.validate_and_parse().unwrap();
+        assert_eq!(search_params.fuzzy, FuzzyMode::Always);
+        assert_eq!(search_params.after.unwrap().values, vec![SortValue::Float(0.5)]);
+
+        // An exact cursor does not fit a fuzzy search's relevance order
+        let params = GoodsQueryParams {
+            goods_name: Some("samsng".to_string()),
+            fuzzy: Some("true".to_string()),
+            cursor: Some(GoodsCursor::new(&[], &good).encode()),
+            ..Default::default()
+        };
+        assert!(params.validate_and_parse().is_err());
+    }
+
+    #[test]
+    fn test_suggest_params_validation() {
+        let params = SuggestQueryParams { prefix: Some(" Sa ".to_string()), limit: None };
+        assert_eq!(params.validate(10, 20).unwrap(), ("Sa".to_string(), 10));
+
+        // The limit is capped rather than rejected, like page sizes
+        let params = SuggestQueryParams { prefix: Some("sams".to_string()), limit: Some("500".to_string()) };
+        assert_eq!(params.validate(10, 20).unwrap().1, 20);
+
+        let params = SuggestQueryParams { prefix: Some("s".to_string()), limit: Some("0".to_string()) };
+        match params.validate(10, 20) {
+            Err(AppError::InvalidFields(errors)) => {
+                let fields: Vec<&str> = errors.iter().map(|e| e.field.as_str()).collect();
+                assert_eq!(fields, vec!["prefix", "limit"]);
+            }
+            other => panic!("expected field errors, got {:?}", other),
+        }
+
+        match SuggestQueryParams::default().validate(10, 20) {
+            Err(AppError::InvalidFields(errors)) => {
+                assert_eq!(errors[0].code, "required");
+                assert_eq!(errors[0].received_value, None);
+            }
+            other => panic!("expected missing prefix, got {:?}", other),
+        }
+
+        let params = HashMap::from([("prefx".to_string(), "sa".to_string())]);
+        assert!(SuggestQueryParams::from_query(params.clone(), true).is_err());
+        assert!(SuggestQueryParams::from_query(params, false).is_ok());
+    }
+
+    #[test]
+    fn test_tag_filters() {
+        let params = extract_query_params(query(&[("tag", "organic"), ("tag", "frozen"), ("tag_mode", "none")]), true, &GOODS_QUERY_PARAMS).unwrap();
+        assert_eq!(params.tag, vec!["organic", "frozen"]);
+        let search_params = params.validate_and_parse().unwrap();
+        assert_eq!(search_params.tags, vec!["organic", "frozen"]);
+        assert_eq!(search_params.tag_mode, TagMode::None);
+
+        let sql = crate::tables::GoodsTable::search_sql(&search_params);
+        assert!(sql.contains("NOT (COALESCE(description, '{}') && $1)"));
+
+        // All tags must be present unless told otherwise
+        let search_params = extract_query_params(query(&[("tag", "organic")]), true, &GOODS_QUERY_PARAMS)
+            .unwrap()
+            .validate_and_parse()
+            .unwrap();
+        assert_eq!(search_params.tag_mode, TagMode::All);
+        assert!(crate::tables::GoodsTable::search_sql(&search_params).contains("description @> $1"));
+
+        let params = GoodsQueryParams { tag: vec!["fragile".to_string()], tag_mode: Some("any".to_string()), ..Default::default() };
+        let search_params = params.validate_and_parse().unwrap();
+        assert!(crate::tables::GoodsTable::search_sql(&search_params).contains("description && $1"));
+
+        let params = GoodsQueryParams {
+            goods_name: Some("*".to_string()),
+            tag_mode: Some("any".to_string()),
+            ..Default::default()
+        };
+        assert!(params.validate_and_parse().is_err());
+
+        let params = GoodsQueryParams { tag: vec!["organic".to_string()], tag_mode: Some("some".to_string()), ..Default::default() };
+        assert!(params.validate_and_parse().is_err());
+
+        let params = GoodsQueryParams { tag: vec!["<b>".to_string()], ..Default::default() };
+        assert!(params.validate_and_parse().is_err());
+    }
+
+    #[test]
+    fn test_facets_parsing() {
+        let params = GoodsQueryParams {
+            goods_name: Some("*".to_string()),
+            facets: Some("price, tag,mass_base,price".to_string()),
+            ..Default::default()
+        };
+        let search_params = params.validate_and_parse().unwrap();
+        assert_eq!(search_params.facets, vec![Facet::Price, Facet::Tag, Facet::MassBase]);
+
+        let params = GoodsQueryParams {
+            goods_name: Some("*".to_string()),
+            facets: Some("price,colour".to_string()),
+            ..Default::default()
+        };
+        match params.validate_and_parse() {
+            Err(AppError::InvalidFields(errors)) => {
+                assert_eq!(errors[0].field, "facets");
+                assert!(errors[0].message.contains("Unknown facet 'colour'"));
+            }
+            other => panic!("expected invalid facets, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_stats_accept_only_filters() {
+        let params = extract_query_params(
+            query(&[("max_price", "100"), ("tag", "organic"), ("tag", "frozen"), ("filter", "mass_g lt 50")]),
+            true,
+            &FILTER_QUERY_PARAMS,
+        )
+        .unwrap();
+        let search_params = params.validate_and_parse().unwrap();
+        assert_eq!(search_params.max_price, Some(Decimal::from(100)));
+        assert_eq!(search_params.tags, vec!["organic", "frozen"]);
+        assert!(search_params.filter.is_some());
+
+        // Paging and sorting mean nothing for statistics
+        for key in ["sort", "page", "cursor", "limit", "fields"] {
+            assert!(extract_query_params(query(&[(key, "1")]), true, &FILTER_QUERY_PARAMS).is_err(), "{}", key);
+        }
+    }
+
+    #[test]
+    fn test_filter_expression_parsing() {
+        let filter = parse_filter("(price lt 10 or mass_g lt 50) and not goods_name co \"refill\"").unwrap();
+        assert_eq!(
+            filter,
+            FilterExpr::And(
+                Box::new(FilterExpr::Or(
+                    Box::new(FilterExpr::Compare {
+                        column: GoodsColumn::Price,
+                        op: FilterOp::Lt,
+                        value: SortValue::Decimal(Decimal::from(10)),
+                    }),
+                    Box::new(FilterExpr::Compare {
+                        column: GoodsColumn::MassG,
+                        op: FilterOp::Lt,
+                        value: SortValue::Decimal(Decimal::from(50)),
+                    }),
+                )),
+                Box::new(FilterExpr::Not(Box::new(FilterExpr::Compare {
+                    column: GoodsColumn::GoodsName,
+                    op: FilterOp::Contains,
+                    value: SortValue::Text("refill".to_string()),
+                }))),
+            )
+        );
+
+        // `and` binds tighter than `or`; keywords ignore case
+        match parse_filter("goods_id eq 1 OR goods_id eq 2 AND mass_base gt 1").unwrap() {
+            FilterExpr::Or(_, right) => assert!(matches!(*right, FilterExpr::And(..))),
+            other => panic!("expected or at the top, got {:?}", other),
+        }
+
+        assert_eq!(
+            parse_filter("material_code in (A100, 'B 200', \"C\\\"300\")").unwrap(),
+            FilterExpr::In {
+                column: GoodsColumn::MaterialCode,
+                values: vec![
+                    SortValue::Text("A100".to_string()),
+                    SortValue::Text("B 200".to_string()),
+                    SortValue::Text("C\"300".to_string()),
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn test_filter_expression_errors() {
+        let message = |input: &str| parse_filter(input).unwrap_err().to_string();
+
+        assert!(message("colour eq red").contains("cannot filter on 'colour'"));
+        assert!(message("goods_nme eq x").contains("Did you mean 'goods_name'?"));
+        assert!(message("description co organic").contains("cannot filter on 'description'"));
+        assert!(message("price co 10").contains("'co' does not apply to price"));
+        assert!(message("goods_name lt x").contains("'lt' does not apply to goods_name"));
+        assert!(message("price lt ten").contains("price expects a decimal number"));
+        assert!(message("goods_id in (1, x)").contains("goods_id expects an integer"));
+        assert!(message("price like 10").contains("unknown operator 'like'"));
+        assert_eq!(message("(price lt 10"), "Invalid filter at position 13: expected ')' but found the end of the filter");
+        assert_eq!(message("price lt 10 price"), "Invalid filter at position 13: unexpected 'price'");
+        assert!(message("goods_name eq \"open").contains("unterminated string"));
+        assert!(message("price lt 10; DROP TABLE goods").contains("unexpected character ';'"));
+        assert!(message("").contains("filter is empty"));
+
+        // Size limits keep a single filter from building an arbitrarily large query
+        let deep = format!("{}price lt 1{}", "(".repeat(20), ")".repeat(20));
+        assert!(message(&deep).contains("nested more than"));
+        let values = vec!["1"; 150].join(",");
+        assert!(message(&format!("goods_id in ({})", values)).contains("more than 100"));
+    }
+
+    #[test]
+    fn test_invalid_filter_is_a_field_error() {
+        let params = GoodsQueryParams { filter: Some("price lt".to_string()), ..Default::default() };
+        match params.validate_and_parse() {
+            Err(AppError::InvalidFields(errors)) => {
+                assert_eq!(errors[0].field, "filter");
+                assert_eq!(errors[0].code, "invalid_format");
+            }
+            other => panic!("expected invalid filter, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_search_document_matches_query_parameters() {
+        let get = extract_query_params(
+            query(&[
+                ("goods_name", "tea"),
+                ("goods_name_match", "contains"),
+                ("min_price", "10.5"),
+                ("tag", "organic"),
+                ("tag", "fair-trade"),
+                ("tag_mode", "any"),
+                ("filter", "mass_g lt 500"),
+                ("sort", "-price,goods_name"),
+                ("page", "2"),
+                ("page_size", "5"),
+                ("facets", "price,tag"),
+                ("fields", "goods_id,price"),
+            ]),
+            true,
+            &GOODS_QUERY_PARAMS,
+        )
+        .unwrap();
+        let document: GoodsSearchRequest = serde_json::from_str(
+            r#"{
+                "filters": {
+                    "goods_name": "tea",
+                    "goods_name_match": "contains",
+                    "min_price": 10.5,
+                    "tag": ["organic", "fair-trade"],
+                    "tag_mode": "any",
+                    "filter": "mass_g lt 500"
+                },
+                "sort": ["-price", "goods_name"],
+                "pagination": { "page": 2, "page_size": 5 },
+                "facets": ["price", "tag"],
+                "fields": ["goods_id", "price"]
+            }"#,
+        )
+        .unwrap();
+        let post = extract_query_params(Query(document.into_query_pairs()), true, &GOODS_QUERY_PARAMS).unwrap();
+
+        assert_eq!(get.validate_and_parse().unwrap(), post.validate_and_parse().unwrap());
+
+        // Invalid values are reported exactly as for query parameters
+        let document: GoodsSearchRequest =
+            serde_json::from_str(r#"{"filters": {"goods_id": 3000000000, "fuzzy": true}, "pagination": {"limit": 0}}"#).unwrap();
+        let pairs = document.into_query_pairs();
+        assert!(pairs.contains(&("fuzzy".to_string(), "true".to_string())));
+        match extract_query_params(Query(pairs), true, &GOODS_QUERY_PARAMS).unwrap().validate_and_parse() {
+            Err(AppError::InvalidFields(errors)) => {
+                let fields: Vec<&str> = errors.iter().map(|error| error.field.as_str()).collect();
+                assert_eq!(fields, vec!["goods_id", "limit"]);
+            }
+            other => panic!("expected invalid fields, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_search_document_is_typed() {
+        let parse = |body: &str| serde_json::from_str::<GoodsSearchRequest>(body);
+        assert!(parse("{}").is_ok());
+        assert!(parse(r#"{"filters": {"colour": "red"}}"#).is_err());
+        assert!(parse(r#"{"limit": 10}"#).is_err());
+        assert!(parse(r#"{"pagination": {"limit": "ten"}}"#).is_err());
+        assert!(parse(r#"{"filters": {"price": "cheap"}}"#).is_err());
+        assert!(parse(r#"{"sort": "-price"}"#).is_err());
+    }
+
+    #[test]
+    fn test_fields_selection() {
+        let params = GoodsQueryParams {
+            goods_name: Some("*".to_string()),
+            fields: Some("goods_name, goods_id,goods_name,relevance".to_string()),
+            ..Default::default()
+        };
+        let fields = params.validate_and_parse().unwrap().fields;
+        assert_eq!(fields, vec![GoodsColumn::GoodsName, GoodsColumn::GoodsId, GoodsColumn::Relevance]);
+
+        let error = parse_fields("goods_id,goodsname").unwrap_err().to_string();
+        assert!(error.contains("Unknown field 'goodsname'. Did you mean 'goods_name'?"));
+
+        let params = GoodsQueryParams { goods_name: Some("*".to_string()), fields: Some("price,colour".to_string()), ..Default::default() };
+        match params.validate_and_parse() {
+            Err(AppError::InvalidFields(errors)) => assert_eq!(errors[0].field, "fields"),
+            other => panic!("expected invalid fields, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_exports_are_never_paginated() {
+        assert!(extract_query_params(query(&[("min_price", "5"), ("sort", "-price")]), true, &EXPORT_QUERY_PARAMS).is_ok());
+        for key in ["limit", "page", "cursor", "facets"] {
+            assert!(extract_query_params(query(&[(key, "1")]), true, &EXPORT_QUERY_PARAMS).is_err());
+        }
+    }
+}
@@ -4,17 +4,47 @@ use axum::{
     response::{IntoResponse, Response},
     Json,
 };
+use crate::error::FieldError;
+use crate::tables::Facets;
 use chrono::{DateTime, Utc};
-use serde::{Deserialize, Serialize};
+use serde::{ser::SerializeMap, Deserialize, Serialize, Serializer};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ApiResponse<T> {
     pub success: bool,
     pub message: String,
     pub data: Option<T>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pagination: Option<Pagination>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub search: Option<SearchInfo>,
+    // Counts per facet, when requested with facets=
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub facets: Option<Facets>,
     pub timestamp: DateTime<Utc>,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Pagination {
+    pub total_count: i64,
+    pub page: i64,
+    pub page_size: i64,
+    pub offset: i64,
+    pub has_more: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+}
+
+// How a text search matched: "exact", or "fuzzy" by trigram similarity. `fuzzy_fallback`
+// is set when the exact search found nothing and the results come from fuzzy matching.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SearchInfo {
+    pub match_mode: String,
+    pub fuzzy_fallback: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fuzzy_threshold: Option<f32>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct HealthResponse {
     pub status: String,
@@ -25,10 +55,71 @@ pub struct HealthResponse {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ErrorResponse {
     pub success: bool,
+    // Stable machine-readable error code, see AppError::code
+    pub code: String,
     pub error: String,
+    // Per-parameter details for validation errors
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<FieldError>,
     pub timestamp: DateTime<Utc>,
 }
 
+// Result of a batch lookup: the goods found plus the identifiers that matched nothing
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BatchLookupResponse<T> {
+    pub goods: Vec<T>,
+    pub not_found: BatchNotFound,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct BatchNotFound {
+    pub goods_ids: Vec<i32>,
+    pub material_codes: Vec<String>,
+}
+
+// An item of a response, either whole or limited to a sparse fieldset
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum Fieldset<T> {
+    Full(T),
+    Sparse(SparseItem),
+}
+
+// The requested fields of an item, written in the order they were requested
+#[derive(Debug)]
+pub struct SparseItem(Vec<(String, serde_json::Value)>);
+
+impl Serialize for SparseItem {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.0.len()))?;
+        for (name, value) in &self.0 {
+            map.serialize_entry(name, value)?;
+        }
+        map.end()
+    }
+}
+
+// Limit every item to `fields`, or keep them whole when no fields are requested. Fields
+// an item does not have, such as relevance outside a text search, are left out.
+pub fn select_fields<T: Serialize>(items: Vec<T>, fields: &[String]) -> Vec<Fieldset<T>> {
+    items.into_iter().map(|item| select_item(item, fields)).collect()
+}
+
+pub fn select_item<T: Serialize>(item: T, fields: &[String]) -> Fieldset<T> {
+    if fields.is_empty() {
+        return Fieldset::Full(item);
+    }
+    match serde_json::to_value(&item) {
+        Ok(serde_json::Value::Object(mut values)) => Fieldset::Sparse(SparseItem(
+            fields
+                .iter()
+                .filter_map(|field| values.remove(field).map(|value| (field.clone(), value)))
+                .collect(),
+        )),
+        _ => Fieldset::Full(item),
+    }
+}
+
 impl<T> ApiResponse<T>
 where
     T: Serialize,
@@ -38,29 +129,57 @@ where
             success: true,
             message: message.to_string(),
             data: Some(data),
+            pagination: None,
+            search: None,
+            facets: None,
             timestamp: Utc::now(),
         }
     }
+
+    pub fn with_pagination(mut self, pagination: Pagination) -> Self {
+        self.pagination = Some(pagination);
+        self
+    }
+
+    pub fn with_search(mut self, search: Option<SearchInfo>) -> Self {
+        self.search = search;
+        self
+    }
+
+    pub fn with_facets(mut self, facets: Option<Facets>) -> Self {
+        self.facets = facets;
+        self
+    }
+}
+
+impl Pagination {
+    pub fn new(total_count: i64, page_size: i64, offset: i64, has_more: bool) -> Self {
+        Self {
+            total_count,
+            page: offset / page_size + 1,
+            page_size,
+            offset,
+            has_more,
+            next_cursor: None,
+        }
+    }
+
+    pub fn with_next_cursor(mut self, next_cursor: Option<String>) -> Self {
+        self.next_cursor = next_cursor;
+        self
+    }
 }
 
 impl ErrorResponse {
-    pub fn new(error: &str) -> Self {
+    pub fn new(code: &str, error: &str) -> Self {
         Self {
             success: false,
+            code: code.to_string(),
             error: error.to_string(),
+            errors: Vec::new(),
             timestamp: Utc::now(),
         }
     }
-
-    pub fn bad_request(error: &str) -> Response {
-        let error_response = ErrorResponse::new(error);
-        (StatusCode::BAD_REQUEST, Json(error_response)).into_response()
-    }
-
-    pub fn internal_server_error(error: &str) -> Response {
-        let error_response = ErrorResponse::new(error);
-        (StatusCode::INTERNAL_SERVER_ERROR, Json(error_response)).into_response()
-    }
 }
 
 impl HealthResponse {
@@ -107,17 +226,32 @@ impl IntoResponse for HealthResponse {
     }
 }
 
-impl IntoResponse for ErrorResponse {
-    fn into_response(self) -> Response {
-        (StatusCode::BAD_REQUEST, Json(self)).into_response()
-    }
-}
-
 // Helper functions for common responses
 pub fn success_response<T: Serialize>(data: T, message: &str) -> Response {
     ApiResponse::success(data, message).into_response()
 }
 
+pub fn created_response<T: Serialize>(data: T, message: &str) -> Response {
+    (StatusCode::CREATED, Json(ApiResponse::success(data, message))).into_response()
+}
+
 pub fn health_response(database_connected: bool) -> Response {
     HealthResponse::new(database_connected).into_response()
-}   
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tables::Good;
+
+    #[test]
+    fn test_select_fields() {
+        // Only the requested fields are written, in the requested order
+        let good = Good { goods_id: 7, goods_name: "Tea".to_string(), ..Default::default() };
+        let names = vec!["goods_name".to_string(), "goods_id".to_string(), "relevance".to_string()];
+        let json = serde_json::to_string(&select_fields(vec![good.clone()], &names)).unwrap();
+        assert_eq!(json, r#"[{"goods_name":"Tea","goods_id":7}]"#);
+        let json = serde_json::to_value(select_fields(vec![good.clone()], &[])).unwrap();
+        assert_eq!(json, serde_json::json!([good]));
+    }
+}
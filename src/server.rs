@@ -1,22 +1,52 @@
 // src/server.rs
+use crate::auth::authorized;
+use crate::cache::{CacheStats, TtlCache};
 use crate::config::AppConfig;
 use crate::database::Database;
-use crate::request::extract_query_params;
-use crate::response::{ErrorResponse, success_response, health_response};
+use crate::error::AppError;
+use crate::format::{format_param, ResponseFormat};
+use crate::http_cache::cached;
+use crate::tables::{
+    Facets, FuzzyMode, Good, GoodsColumn, GoodsPage, GoodsSearchParams, GoodsStats, Suggestion, TagCount,
+};
+use crate::v2;
+use crate::request::{
+    extract_query_params, parse_goods_id, parse_material_code, GoodPatchRequest,
+    GoodWriteRequest, GoodsBatchRequest, GoodsCursor, GoodsQueryParams, GoodsSearchRequest,
+    SuggestQueryParams, EXPORT_QUERY_PARAMS, FILTER_QUERY_PARAMS, GOODS_QUERY_PARAMS,
+};
+use crate::response::{
+    BatchLookupResponse, BatchNotFound, Pagination, SearchInfo, created_response,
+    ApiResponse, select_fields, select_item, success_response, health_response,
+};
 use axum::{
-    extract::{Query, State},
+    extract::{rejection::JsonRejection, Path, Query, State},
+    http::{header, HeaderMap, HeaderValue},
+    middleware,
     response::Response,
-    routing::get,
-    Router,
+    routing::{get, post, put},
+    Json, Router,
 };
-use std::collections::HashMap;
+use futures::{Stream, StreamExt};
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
 use tower::ServiceBuilder;
 use tower_http::cors::CorsLayer;
 use tracing::{error, info, warn};
 
+// RFC 9745 Deprecation date (2026-10-17) and RFC 8594 Sunset date for the unversioned v1 API
+const V1_DEPRECATION: &str = "@1792195200";
+const V1_SUNSET: &str = "Fri, 30 Apr 2027 00:00:00 GMT";
+const V1_SUCCESSOR_LINK: &str = "</v2/goods>; rel=\"successor-version\"";
+
 #[derive(Clone)]
 pub struct AppState {
     pub database: Database,
+    pub config: AppConfig,
+    // Suggestions keyed by lowercased prefix and limit
+    pub suggest_cache: TtlCache<(String, i64), Vec<Suggestion>>,
+    // Search result pages keyed by GoodsSearchParams::cache_key
+    pub search_cache: TtlCache<String, GoodsPage>,
 }
 
 pub struct Server {
@@ -33,8 +63,22 @@ impl Server {
         let host = self.config.server.host.clone();
         let port = self.config.server.port;
         
+        let suggest_cache = TtlCache::new(
+            Duration::from_secs(self.config.suggest.cache_ttl_secs),
+            self.config.suggest.cache_max_entries,
+        );
+        let search_cache = TtlCache::new(
+            Duration::from_secs(self.config.search_cache.ttl_secs),
+            self.config.search_cache.max_entries,
+        )
+        .with_max_bytes(self.config.search_cache.max_bytes, |key: &String, page: &GoodsPage| {
+            key.len() + page.approximate_size()
+        });
         let app_state = AppState {
             database: self.database,
+            config: self.config,
+            suggest_cache,
+            search_cache,
         };
 
         let app = Self::create_router(app_state);
@@ -49,10 +93,43 @@ impl Server {
     }
 
     fn create_router(state: AppState) -> Router {
+        // GET routes answer conditional requests and carry the configured Cache-Control
+        let cache_control = state.config.cache_control.clone();
+        // Writes and admin endpoints need the admin token
+        let admin_token = state.config.auth.admin_token.clone();
+        let admin_token = admin_token.as_deref();
+
+        // Original unversioned goods API, kept unchanged but marked deprecated
+        let v1 = Router::new()
+            .route(
+                "/goods",
+                cached(get(get_goods), &cache_control.goods).merge(authorized(post(create_good), admin_token)),
+            )
+            .route("/goods/batch", post(get_goods_batch))
+            .route("/goods/search", post(post_goods_search))
+            .route("/goods/suggest", cached(get(get_goods_suggestions), &cache_control.suggest))
+            .route("/goods/tags", cached(get(get_goods_tags), &cache_control.tags))
+            .route("/goods/stats", cached(get(get_goods_stats), &cache_control.stats))
+            .route("/goods/export", cached(get(export_goods), &cache_control.export))
+            .route(
+                "/goods/{goods_id}",
+                cached(get(get_good_by_id), &cache_control.lookup).merge(authorized(
+                    put(update_good).patch(patch_good).delete(delete_good),
+                    admin_token,
+                )),
+            )
+            .route(
+                "/goods/by-material/{material_code}",
+                cached(get(get_good_by_material_code), &cache_control.lookup),
+            )
+            .layer(middleware::map_response(add_v1_deprecation_headers));
+
         Router::new()
             .route("/", get(api_health))
             .route("/health", get(database_health))
-            .route("/goods", get(get_goods))
+            .route("/admin/cache", authorized(get(get_cache_stats).delete(flush_caches), admin_token))
+            .merge(v1)
+            .nest("/v2", v2::router(&cache_control, admin_token))
             .layer(
                 ServiceBuilder::new()
                     .layer(CorsLayer::permissive())
@@ -74,6 +151,26 @@ async fn api_health() -> Response {
     )
 }
 
+// Route: GET /admin/cache - Size and hit/miss counters of the in-memory caches
+async fn get_cache_stats(State(state): State<AppState>) -> Response {
+    success_response(cache_stats(&state), "Cache statistics retrieved successfully")
+}
+
+// Route: DELETE /admin/cache - Drop every cached search and suggestion
+async fn flush_caches(State(state): State<AppState>) -> Response {
+    let searches = state.search_cache.clear();
+    let suggestions = state.suggest_cache.clear();
+    info!("Flushed {} cached searches and {} cached suggestions", searches, suggestions);
+    success_response(cache_stats(&state), "Caches flushed successfully")
+}
+
+fn cache_stats(state: &AppState) -> HashMap<&'static str, CacheStats> {
+    HashMap::from([
+        ("search", state.search_cache.stats()),
+        ("suggest", state.suggest_cache.stats()),
+    ])
+}
+
 // Route: GET /health - Database health check
 async fn database_health(State(state): State<AppState>) -> Response {
     info!("Database health check requested");
@@ -93,51 +190,555 @@ async fn database_health(State(state): State<AppState>) -> Response {
 // Route: GET /goods - Get goods with query parameters
 async fn get_goods(
     State(state): State<AppState>,
-    query: Query<HashMap<String, String>>,
-) -> Response {
+    headers: HeaderMap,
+    query: Query<Vec<(String, String)>>,
+) -> Result<Response, AppError> {
     info!("Goods search requested with params: {:?}", query.0);
+    let format = ResponseFormat::negotiate(&headers, format_param(&query.0))?;
 
     // Extract and validate query parameters
-    let query_params = extract_query_params(query);
-    
+    let query_params = extract_query_params(query, state.config.server.strict_query_params, &GOODS_QUERY_PARAMS)
+        .inspect_err(|error| warn!("Rejected query parameters: {}", error))?;
+
+    let result = search_goods(&state, query_params).await?;
+    search_response(result, format)
+}
+
+// Route: POST /goods/search - Search goods with a JSON document instead of query parameters
+async fn post_goods_search(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    query: Query<Vec<(String, String)>>,
+    body: Result<Json<GoodsSearchRequest>, JsonRejection>,
+) -> Result<Response, AppError> {
+    let format = ResponseFormat::negotiate(&headers, format_param(&query.0))?;
+    let Json(request) = body?;
+    info!("Goods search requested with document: {:?}", request);
+
+    // The document is checked like strict query parameters, whatever the configuration
+    let query_params = extract_query_params(Query(request.into_query_pairs()), true, &GOODS_QUERY_PARAMS)?;
+
+    let result = search_goods(&state, query_params).await?;
+    search_response(result, format)
+}
+
+fn search_response(result: GoodsSearchResult, format: ResponseFormat) -> Result<Response, AppError> {
+    let fields: Vec<String> = result.fields.iter().map(|column| column.name().to_string()).collect();
+    let columns: Vec<String> = result.columns().iter().map(|column| column.name().to_string()).collect();
+    let envelope = ApiResponse::success(select_fields(result.goods, &fields), "Goods retrieved successfully")
+        .with_pagination(result.pagination)
+        .with_search(result.search)
+        .with_facets(result.facets);
+    format.respond(envelope, &columns)
+}
+
+// Search shared by every version of GET /goods and POST /goods/search
+// One page of goods plus everything the search envelope reports about it
+pub struct GoodsSearchResult {
+    pub goods: Vec<Good>,
+    // Sparse fieldset the goods are to be returned with; empty for every field
+    pub fields: Vec<GoodsColumn>,
+    pub pagination: Pagination,
+    pub search: Option<SearchInfo>,
+    pub facets: Option<Facets>,
+    // Whether the goods carry a relevance score: q= searches and fuzzy matches do
+    pub ranked: bool,
+}
+
+impl GoodsSearchResult {
+    // Columns the goods are written with: the sparse fieldset, or every column plus the
+    // relevance of a ranked search
+    pub fn columns(&self) -> Vec<GoodsColumn> {
+        if !self.fields.is_empty() {
+            return self.fields.clone();
+        }
+        let mut columns = GoodsColumn::ALL.to_vec();
+        if self.ranked {
+            columns.push(GoodsColumn::Relevance);
+        }
+        columns
+    }
+}
+
+pub async fn search_goods(state: &AppState, query_params: GoodsQueryParams) -> Result<GoodsSearchResult, AppError> {
     // Check if no parameters provided
-    if query_params.goods_id.is_none()
-        && query_params.material_code.is_none()
-        && query_params.goods_name.is_none()
-        && query_params.price.is_none()
-        && query_params.volumn_l.is_none()
-        && query_params.mass_g.is_none()
-        && query_params.min_volumn_l.is_none()
-        && query_params.max_volumn_l.is_none()
-        && query_params.min_mass_g.is_none()
-        && query_params.max_mass_g.is_none()
-        && query_params.min_price.is_none()
-        && query_params.max_price.is_none()
-    {
+    if !query_params.has_filters() {
         warn!("No query parameters provided for goods search");
-        return ErrorResponse::bad_request(
-            "Query parameters required. Use goods_name=* or material_code=* to get all goods, or specify search criteria like goods_id, material_code, goods_name, price, volumn_l, mass_g, min_volumn_l, max_volumn_l, min_mass_g, max_mass_g, min_price, max_price"
-        );
+        return Err(AppError::validation(format!(
+            "Query parameters required. Use goods_name=* or material_code=* to list every good, or filter with any of: {}",
+            FILTER_QUERY_PARAMS.join(", ")
+        )));
     }
 
-    // Validate and parse query parameters (SQL injection protection)
-    let search_params = match query_params.validate_and_parse() {
-        Ok(params) => params,
-        Err(error) => {
-            warn!("Invalid query parameters: {}", error);
-            return ErrorResponse::bad_request(&format!("Invalid query parameters: {}", error));
-        }
-    };
+    // Validate and parse query parameters
+    let mut search_params = query_params.validate_and_parse().inspect_err(|error| {
+        warn!("Invalid query parameters: {}", error);
+    })?;
+    let search_config = &state.config.search;
+    configure_fuzzy(state, &mut search_params)?;
+    if !search_config.fuzzy_fallback && search_params.fuzzy == FuzzyMode::Auto {
+        search_params.fuzzy = FuzzyMode::Off;
+    }
 
     // Perform database search
-    match state.database.goods_table.search(search_params).await {
-        Ok(goods) => {
-            info!("Found {} goods matching search criteria", goods.len());
-            success_response(goods, "Goods retrieved successfully")
-        }
-        Err(e) => {
-            error!("Database error during goods search: {}", e);
-            ErrorResponse::internal_server_error("Failed to search goods")
+    let (limit, offset) = (search_params.limit, search_params.offset);
+    let is_text_search = !search_params.fuzzy_terms().is_empty();
+    let has_text_query = search_params.text_query.is_some();
+    let fields = search_params.fields.clone();
+    let mut facet_params = search_params.clone();
+    let page = cached_search(state, search_params).await?;
+
+    if page.fell_back_to_fuzzy {
+        info!("Exact search found nothing, fell back to fuzzy matching");
+    }
+    info!("Found {} of {} goods matching search criteria", page.goods.len(), page.total);
+    let next_cursor = page
+        .goods
+        .last()
+        .filter(|_| page.has_more)
+        .map(|good| GoodsCursor::new(&page.sort, good).with_fuzzy(page.fuzzy).encode());
+    let pagination = Pagination::new(page.total, limit, offset, page.has_more)
+        .with_next_cursor(next_cursor);
+    let search = is_text_search.then(|| SearchInfo {
+        match_mode: if page.fuzzy { "fuzzy" } else { "exact" }.to_string(),
+        fuzzy_fallback: page.fell_back_to_fuzzy,
+        fuzzy_threshold: page.fuzzy.then_some(search_config.fuzzy_threshold),
+    });
+
+    // Facets count the same goods as the results, including after a fuzzy fallback
+    let facets = if facet_params.facets.is_empty() {
+        None
+    } else {
+        if page.fuzzy {
+            facet_params.fuzzy = FuzzyMode::Always;
         }
+        let facets = state
+            .database
+            .goods_table
+            .facets(&facet_params)
+            .await
+            .map_err(|e| AppError::database(e, "count facets"))?;
+        Some(facets)
+    };
+
+    Ok(GoodsSearchResult {
+        goods: page.goods,
+        fields,
+        pagination,
+        search,
+        facets,
+        ranked: has_text_query || page.fuzzy,
+    })
+}
+
+// Apply the configured similarity threshold. Without pg_trgm the database cannot match
+// fuzzily, so the fallback is skipped and explicit fuzzy searches are refused.
+fn configure_fuzzy(state: &AppState, search_params: &mut GoodsSearchParams) -> Result<(), AppError> {
+    search_params.fuzzy_threshold = state.config.search.fuzzy_threshold;
+    if state.database.goods_table.fuzzy_matching() {
+        return Ok(());
+    }
+    if search_params.is_fuzzy() {
+        warn!("Fuzzy search requested but pg_trgm is not installed");
+        return Err(AppError::validation("Fuzzy matching is not available on this server; retry with fuzzy=false"));
+    }
+    search_params.fuzzy = FuzzyMode::Off;
+    Ok(())
+}
+
+// Identical searches within the cache TTL are answered without querying the database
+async fn cached_search(state: &AppState, search_params: GoodsSearchParams) -> Result<GoodsPage, AppError> {
+    let key = search_params.cache_key();
+    if let Some(page) = state.search_cache.get(&key) {
+        return Ok(page);
     }
-}
\ No newline at end of file
+
+    // A write that lands while the query runs clears the cache; the page it returns may
+    // predate that write, so it is then not cached
+    let generation = state.search_cache.generation();
+
+    let page = state
+        .database
+        .goods_table
+        .search(search_params)
+        .await
+        .map_err(|e| AppError::database(e, "search goods"))?;
+
+    state.search_cache.insert(generation, key, page.clone());
+    Ok(page)
+}
+
+// Route: GET /goods/suggest - Typeahead suggestions for goods names and material codes
+async fn get_goods_suggestions(
+    State(state): State<AppState>,
+    query: Query<HashMap<String, String>>,
+) -> Result<Response, AppError> {
+    let params = SuggestQueryParams::from_query(query.0, state.config.server.strict_query_params)
+        .inspect_err(|error| warn!("Rejected suggest parameters: {}", error))?;
+    let suggestions = suggest_goods(&state, params).await?;
+    Ok(success_response(suggestions, "Suggestions retrieved successfully"))
+}
+
+pub async fn suggest_goods(state: &AppState, params: SuggestQueryParams) -> Result<Vec<Suggestion>, AppError> {
+    let config = &state.config.suggest;
+    let (prefix, limit) = params.validate(config.default_limit, config.max_limit)?;
+
+    // Matching ignores case, so differently cased prefixes share a cache entry
+    let key = (prefix.to_lowercase(), limit);
+    if let Some(suggestions) = state.suggest_cache.get(&key) {
+        return Ok(suggestions);
+    }
+
+    // Typeahead is only useful when fast: give up once the latency budget is spent. The
+    // database enforces the same budget on the query; this one also covers waiting for a
+    // connection.
+    let budget = Duration::from_millis(config.timeout_ms);
+    let generation = state.suggest_cache.generation();
+    let lookup = state.database.goods_table.suggest(&prefix, limit, budget);
+    let suggestions = tokio::time::timeout(budget, lookup)
+        .await
+        .map_err(|_| {
+            warn!("Suggestions for '{}' exceeded the {} ms budget", prefix, config.timeout_ms);
+            AppError::Timeout(format!("Suggestions took longer than {} ms", config.timeout_ms))
+        })?
+        .map_err(|e| AppError::database(e, "suggest goods"))?;
+
+    state.suggest_cache.insert(generation, key, suggestions.clone());
+    Ok(suggestions)
+}
+
+// Route: GET /goods/tags - Every description tag in use, with the number of goods carrying it
+async fn get_goods_tags(State(state): State<AppState>) -> Result<Response, AppError> {
+    let tags = list_tags(&state).await?;
+    Ok(success_response(tags, "Tags retrieved successfully"))
+}
+
+pub async fn list_tags(state: &AppState) -> Result<Vec<TagCount>, AppError> {
+    info!("Tag listing requested");
+    let tags = state
+        .database
+        .goods_table
+        .tag_counts()
+        .await
+        .map_err(|e| AppError::database(e, "list tags"))?;
+    info!("Found {} distinct tags", tags.len());
+    Ok(tags)
+}
+
+// Route: GET /goods/stats - Summary statistics of the goods matching the /goods filters
+async fn get_goods_stats(
+    State(state): State<AppState>,
+    query: Query<Vec<(String, String)>>,
+) -> Result<Response, AppError> {
+    info!("Goods statistics requested with params: {:?}", query.0);
+
+    let query_params = extract_query_params(query, state.config.server.strict_query_params, &FILTER_QUERY_PARAMS)
+        .inspect_err(|error| warn!("Rejected stats parameters: {}", error))?;
+    let stats = compute_stats(&state, query_params).await?;
+    Ok(success_response(stats, "Statistics computed successfully"))
+}
+
+// Without filters the statistics cover the whole catalog
+pub async fn compute_stats(state: &AppState, query_params: GoodsQueryParams) -> Result<GoodsStats, AppError> {
+    let mut search_params = query_params.validate_and_parse().inspect_err(|error| {
+        warn!("Invalid stats parameters: {}", error);
+    })?;
+    configure_fuzzy(state, &mut search_params)?;
+
+    let stats = state
+        .database
+        .goods_table
+        .stats(&search_params)
+        .await
+        .map_err(|e| AppError::database(e, "compute statistics"))?;
+    info!("Computed statistics over {} goods", stats.count);
+    Ok(stats)
+}
+
+// Route: GET /goods/export - Every good matching the /goods filters, streamed as a JSON
+// array or NDJSON
+async fn export_goods(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    query: Query<Vec<(String, String)>>,
+) -> Result<Response, AppError> {
+    info!("Goods export requested with params: {:?}", query.0);
+    let format = ResponseFormat::negotiate(&headers, format_param(&query.0))?.streamable()?;
+
+    let query_params = extract_query_params(query, state.config.server.strict_query_params, &EXPORT_QUERY_PARAMS)
+        .inspect_err(|error| warn!("Rejected export parameters: {}", error))?;
+    let (goods, fields) = stream_goods(&state, query_params).await?;
+    let fields: Vec<String> = fields.iter().map(|column| column.name().to_string()).collect();
+    format.stream(goods.map(move |good| good.map(|good| select_item(good, &fields))))
+}
+
+// Without filters the export covers the whole catalog. Returns the goods and the sparse
+// fieldset they are to be written with.
+pub async fn stream_goods(
+    state: &AppState,
+    query_params: GoodsQueryParams,
+) -> Result<(impl Stream<Item = Result<Good, AppError>> + Send + 'static, Vec<GoodsColumn>), AppError> {
+    let mut search_params = query_params.validate_and_parse().inspect_err(|error| {
+        warn!("Invalid export parameters: {}", error);
+    })?;
+    configure_fuzzy(state, &mut search_params)?;
+    // Falling back to fuzzy matching needs to know the exact search found nothing, which a
+    // stream cannot wait for, so exports only match fuzzily when asked to
+    if search_params.fuzzy == FuzzyMode::Auto {
+        search_params.fuzzy = FuzzyMode::Off;
+    }
+
+    let fields = search_params.fields.clone();
+    let mut goods = Box::pin(
+        state
+            .database
+            .goods_table
+            .export(search_params)
+            .map(|good| good.map_err(|e| AppError::database(e, "export goods"))),
+    );
+
+    // Wait for the first row before answering, so a failing query still gets an error
+    // status instead of a truncated 200
+    let first = goods.next().await.transpose()?;
+    Ok((futures::stream::iter(first.map(Ok)).chain(goods), fields))
+}
+
+// Route: GET /goods/{goods_id} - Get a single good by id
+async fn get_good_by_id(
+    State(state): State<AppState>,
+    Path(goods_id): Path<String>,
+) -> Result<Response, AppError> {
+    let good = find_good_by_id(&state, &goods_id).await?;
+    Ok(success_response(good, "Good retrieved successfully"))
+}
+
+pub async fn find_good_by_id(state: &AppState, goods_id: &str) -> Result<Good, AppError> {
+    info!("Good lookup requested for goods_id {}", goods_id);
+
+    let goods_id = parse_goods_id(goods_id)?;
+    state
+        .database
+        .goods_table
+        .find_by_id(goods_id)
+        .await
+        .map_err(|e| AppError::database(e, "retrieve good"))?
+        .ok_or_else(|| AppError::not_found(format!("Good with goods_id {} not found", goods_id)))
+}
+
+// Route: GET /goods/by-material/{material_code} - Get a single good by exact material code
+async fn get_good_by_material_code(
+    State(state): State<AppState>,
+    Path(material_code): Path<String>,
+) -> Result<Response, AppError> {
+    let good = find_good_by_material_code(&state, &material_code).await?;
+    Ok(success_response(good, "Good retrieved successfully"))
+}
+
+pub async fn find_good_by_material_code(state: &AppState, material_code: &str) -> Result<Good, AppError> {
+    info!("Good lookup requested for material_code {}", material_code);
+
+    let material_code = parse_material_code(material_code)?;
+    state
+        .database
+        .goods_table
+        .find_by_material_code(&material_code)
+        .await
+        .map_err(|e| AppError::database(e, "retrieve good"))?
+        .ok_or_else(|| AppError::not_found(format!("Good with material_code {} not found", material_code)))
+}
+
+// Route: POST /goods/batch - Resolve many goods by ids and/or material codes at once
+async fn get_goods_batch(
+    State(state): State<AppState>,
+    body: Result<Json<GoodsBatchRequest>, JsonRejection>,
+) -> Result<Response, AppError> {
+    let Json(request) = body?;
+    let result = batch_lookup(&state, request).await?;
+    Ok(success_response(result, "Goods retrieved successfully"))
+}
+
+pub async fn batch_lookup(state: &AppState, request: GoodsBatchRequest) -> Result<BatchLookupResponse<Good>, AppError> {
+    let request = request.validate().inspect_err(|error| {
+        warn!("Invalid batch request: {}", error);
+    })?;
+
+    info!(
+        "Batch lookup requested for {} goods_ids and {} material_codes",
+        request.goods_ids.len(),
+        request.material_codes.len()
+    );
+
+    let goods = state
+        .database
+        .goods_table
+        .find_many(&request.goods_ids, &request.material_codes)
+        .await
+        .map_err(|e| AppError::database(e, "retrieve goods"))?;
+
+    let found_ids: HashSet<i32> = goods.iter().map(|good| good.goods_id).collect();
+    let found_codes: HashSet<&str> = goods.iter().map(|good| good.material_code.as_str()).collect();
+    let not_found = BatchNotFound {
+        goods_ids: request
+            .goods_ids
+            .into_iter()
+            .filter(|id| !found_ids.contains(id))
+            .collect(),
+        material_codes: request
+            .material_codes
+            .into_iter()
+            .filter(|code| !found_codes.contains(code.as_str()))
+            .collect(),
+    };
+    info!(
+        "Batch lookup found {} goods, {} identifiers not found",
+        goods.len(),
+        not_found.goods_ids.len() + not_found.material_codes.len()
+    );
+
+    Ok(BatchLookupResponse { goods, not_found })
+}
+
+// Route: POST /goods - Create a good
+async fn create_good(
+    State(state): State<AppState>,
+    body: Result<Json<GoodWriteRequest>, JsonRejection>,
+) -> Result<Response, AppError> {
+    let Json(request) = body?;
+    let good = insert_good(&state, request).await?;
+    Ok(created_response(good, "Good created successfully"))
+}
+
+pub async fn insert_good(state: &AppState, request: GoodWriteRequest) -> Result<Good, AppError> {
+    require_writer(state)?;
+
+    let good = request.validate().inspect_err(|error| {
+        warn!("Invalid create good request: {}", error);
+    })?;
+
+    let good = state
+        .database
+        .goods_table
+        .insert(good)
+        .await
+        .map_err(|e| AppError::database(e, "create good"))?;
+
+    invalidate_caches(state);
+    info!("Created good {}", good.goods_id);
+    Ok(good)
+}
+
+// Route: PUT /goods/{goods_id} - Replace a good
+async fn update_good(
+    State(state): State<AppState>,
+    Path(goods_id): Path<String>,
+    body: Result<Json<GoodWriteRequest>, JsonRejection>,
+) -> Result<Response, AppError> {
+    let Json(request) = body?;
+    let good = replace_good(&state, &goods_id, request).await?;
+    Ok(success_response(good, "Good updated successfully"))
+}
+
+pub async fn replace_good(state: &AppState, goods_id: &str, request: GoodWriteRequest) -> Result<Good, AppError> {
+    require_writer(state)?;
+
+    let goods_id = parse_goods_id(goods_id)?;
+    let good = request.validate().inspect_err(|error| {
+        warn!("Invalid update good request: {}", error);
+    })?;
+
+    let good = state
+        .database
+        .goods_table
+        .update(goods_id, good)
+        .await
+        .map_err(|e| AppError::database(e, "update good"))?
+        .ok_or_else(|| AppError::not_found(format!("Good with goods_id {} not found", goods_id)))?;
+
+    invalidate_caches(state);
+    info!("Updated good {}", goods_id);
+    Ok(good)
+}
+
+// Route: PATCH /goods/{goods_id} - Update some fields of a good
+async fn patch_good(
+    State(state): State<AppState>,
+    Path(goods_id): Path<String>,
+    body: Result<Json<GoodPatchRequest>, JsonRejection>,
+) -> Result<Response, AppError> {
+    let Json(request) = body?;
+    let good = apply_good_patch(&state, &goods_id, request).await?;
+    Ok(success_response(good, "Good updated successfully"))
+}
+
+pub async fn apply_good_patch(state: &AppState, goods_id: &str, request: GoodPatchRequest) -> Result<Good, AppError> {
+    require_writer(state)?;
+
+    let goods_id = parse_goods_id(goods_id)?;
+    let patch = request.validate().inspect_err(|error| {
+        warn!("Invalid patch good request: {}", error);
+    })?;
+
+    let good = state
+        .database
+        .goods_table
+        .patch(goods_id, patch)
+        .await
+        .map_err(|e| AppError::database(e, "patch good"))?
+        .ok_or_else(|| AppError::not_found(format!("Good with goods_id {} not found", goods_id)))?;
+
+    invalidate_caches(state);
+    info!("Patched good {}", goods_id);
+    Ok(good)
+}
+
+// Route: DELETE /goods/{goods_id} - Delete a good
+async fn delete_good(
+    State(state): State<AppState>,
+    Path(goods_id): Path<String>,
+) -> Result<Response, AppError> {
+    let good = remove_good(&state, &goods_id).await?;
+    Ok(success_response(good, "Good deleted successfully"))
+}
+
+pub async fn remove_good(state: &AppState, goods_id: &str) -> Result<Good, AppError> {
+    require_writer(state)?;
+
+    let goods_id = parse_goods_id(goods_id)?;
+    let good = state
+        .database
+        .goods_table
+        .delete(goods_id)
+        .await
+        .map_err(|e| AppError::database(e, "delete good"))?
+        .ok_or_else(|| AppError::not_found(format!("Good with goods_id {} not found", goods_id)))?;
+
+    invalidate_caches(state);
+    info!("Deleted good {}", goods_id);
+    Ok(good)
+}
+
+// Cached searches and suggestions may include the good that was just written
+fn invalidate_caches(state: &AppState) {
+    state.search_cache.clear();
+    state.suggest_cache.clear();
+}
+
+fn require_writer(state: &AppState) -> Result<(), AppError> {
+    if state.database.goods_table.can_write() {
+        return Ok(());
+    }
+
+    warn!("Write request received but no database writer is configured");
+    Err(AppError::DatabaseUnavailable(
+        "Write access is not configured on this server".to_string(),
+    ))
+}
+
+// v1 spells volume as "volumn"; every v1 response announces its deprecation in favour of /v2
+async fn add_v1_deprecation_headers(mut response: Response) -> Response {
+    let headers = response.headers_mut();
+    headers.insert("deprecation", HeaderValue::from_static(V1_DEPRECATION));
+    headers.insert("sunset", HeaderValue::from_static(V1_SUNSET));
+    headers.insert(header::LINK, HeaderValue::from_static(V1_SUCCESSOR_LINK));
+    response
+}
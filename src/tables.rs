@@ -1,8 +1,38 @@
 // src/tables.rs
+use futures::{Stream, TryStreamExt};
 use serde::{Deserialize, Serialize};
-use sqlx::{FromRow, PgPool};
+use sqlx::{FromRow, PgPool, Postgres, QueryBuilder, Row};
+use std::collections::BTreeMap;
+use std::time::Duration;
+use tracing::warn;
 
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+// Page size used when the client does not ask for one
+pub const DEFAULT_PAGE_SIZE: i64 = 50;
+// Upper bound on rows returned by a single search, whatever the client asks for
+pub const MAX_PAGE_SIZE: i64 = 100;
+
+const GOODS_COLUMNS: &str = "goods_id, material_code, goods_name, description, price, volumn_l, mass_g, mass_base, volumn_base";
+
+// Text search configuration used for goods names and descriptions
+const TEXT_SEARCH_CONFIG: &str = "english";
+
+// Weighted full-text document of a good: name and material code rank above description tags.
+// It is computed for every row a q= search reads; array_to_string is not immutable, so the
+// expression cannot be indexed as it stands.
+fn search_document() -> String {
+    format!(
+        "(setweight(to_tsvector('{config}', goods_name), 'A') \
+        || setweight(to_tsvector('simple', material_code), 'A') \
+        || setweight(to_tsvector('{config}', array_to_string(COALESCE(description, '{{}}'), ' ')), 'B'))",
+        config = TEXT_SEARCH_CONFIG
+    )
+}
+// Minimum pg_trgm word similarity for a fuzzy match, unless configured otherwise
+pub const DEFAULT_FUZZY_THRESHOLD: f32 = 0.3;
+
+// Searches with a sparse fieldset read only some columns; the others keep their defaults
+#[derive(Debug, Clone, Default, Serialize, Deserialize, FromRow)]
+#[sqlx(default)]
 pub struct Good {
     pub goods_id: i32,
     pub material_code: String,
@@ -13,13 +43,256 @@ pub struct Good {
     pub mass_g: rust_decimal::Decimal,
     pub mass_base: i16,
     pub volumn_base: i16,
+    // Full-text rank for `q=` searches, or trigram similarity for fuzzy searches
+    #[sqlx(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub relevance: Option<f32>,
 }
 
+// Values for a new good, or for fully replacing an existing one (goods_id is assigned by the database)
 #[derive(Debug, Clone)]
+pub struct NewGood {
+    pub material_code: String,
+    pub goods_name: String,
+    pub description: Option<Vec<String>>,
+    pub price: rust_decimal::Decimal,
+    pub volumn_l: rust_decimal::Decimal,
+    pub mass_g: rust_decimal::Decimal,
+    pub mass_base: i16,
+    pub volumn_base: i16,
+}
+
+// Partial update: only the fields that are Some are written.
+// `description: Some(None)` clears the description.
+#[derive(Debug, Clone, Default)]
+pub struct GoodPatch {
+    pub material_code: Option<String>,
+    pub goods_name: Option<String>,
+    pub description: Option<Option<Vec<String>>>,
+    pub price: Option<rust_decimal::Decimal>,
+    pub volumn_l: Option<rust_decimal::Decimal>,
+    pub mass_g: Option<rust_decimal::Decimal>,
+    pub mass_base: Option<i16>,
+    pub volumn_base: Option<i16>,
+}
+
+impl GoodPatch {
+    pub fn is_empty(&self) -> bool {
+        self.material_code.is_none()
+            && self.goods_name.is_none()
+            && self.description.is_none()
+            && self.price.is_none()
+            && self.volumn_l.is_none()
+            && self.mass_g.is_none()
+            && self.mass_base.is_none()
+            && self.volumn_base.is_none()
+    }
+}
+
+// Columns of `Good`, plus the computed search relevance. Column names only ever reach
+// SQL through this enum.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GoodsColumn {
+    GoodsId,
+    MaterialCode,
+    GoodsName,
+    Description,
+    Price,
+    VolumnL,
+    MassG,
+    MassBase,
+    VolumnBase,
+    // ts_rank of a full-text search or similarity of a fuzzy one; not a table column,
+    // so not part of ALL
+    Relevance,
+}
+
+impl GoodsColumn {
+    pub const ALL: [GoodsColumn; 9] = [
+        GoodsColumn::GoodsId,
+        GoodsColumn::MaterialCode,
+        GoodsColumn::GoodsName,
+        GoodsColumn::Description,
+        GoodsColumn::Price,
+        GoodsColumn::VolumnL,
+        GoodsColumn::MassG,
+        GoodsColumn::MassBase,
+        GoodsColumn::VolumnBase,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            GoodsColumn::GoodsId => "goods_id",
+            GoodsColumn::MaterialCode => "material_code",
+            GoodsColumn::GoodsName => "goods_name",
+            GoodsColumn::Description => "description",
+            GoodsColumn::Price => "price",
+            GoodsColumn::VolumnL => "volumn_l",
+            GoodsColumn::MassG => "mass_g",
+            GoodsColumn::MassBase => "mass_base",
+            GoodsColumn::VolumnBase => "volumn_base",
+            GoodsColumn::Relevance => "relevance",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|column| column.name() == name)
+    }
+
+    // Expression used in ORDER BY and keyset comparisons; description is nullable
+    fn sort_expression(&self) -> &'static str {
+        match self {
+            GoodsColumn::Description => "COALESCE(description, '{}')",
+            column => column.name(),
+        }
+    }
+
+    pub fn sort_value(&self, good: &Good) -> SortValue {
+        match self {
+            GoodsColumn::GoodsId => SortValue::Integer(good.goods_id.into()),
+            GoodsColumn::MaterialCode => SortValue::Text(good.material_code.clone()),
+            GoodsColumn::GoodsName => SortValue::Text(good.goods_name.clone()),
+            GoodsColumn::Description => SortValue::TextArray(good.description.clone().unwrap_or_default()),
+            GoodsColumn::Price => SortValue::Decimal(good.price),
+            GoodsColumn::VolumnL => SortValue::Decimal(good.volumn_l),
+            GoodsColumn::MassG => SortValue::Decimal(good.mass_g),
+            GoodsColumn::MassBase => SortValue::Integer(good.mass_base.into()),
+            GoodsColumn::VolumnBase => SortValue::Integer(good.volumn_base.into()),
+            GoodsColumn::Relevance => SortValue::Float(good.relevance.unwrap_or_default()),
+        }
+    }
+
+    // Whether a value (e.g. decoded from a cursor) has the type of this column
+    pub fn accepts(&self, value: &SortValue) -> bool {
+        std::mem::discriminant(&self.sort_value(&Good::default())) == std::mem::discriminant(value)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SortKey {
+    pub column: GoodsColumn,
+    pub descending: bool,
+}
+
+// Value of a sort column for one row, as carried by keyset cursors
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum SortValue {
+    Integer(i64),
+    Float(f32),
+    Decimal(rust_decimal::Decimal),
+    Text(String),
+    TextArray(Vec<String>),
+}
+
+// Position just after the last row of a page: its sort key values plus goods_id as tie-breaker
+#[derive(Debug, Clone, PartialEq)]
+pub struct Keyset {
+    pub values: Vec<SortValue>,
+    pub goods_id: i32,
+}
+
+// Boolean filter expression from `filter=`, parsed by request::parse_filter. Values have
+// the type of their column, like cursor values (see GoodsColumn::accepts).
+#[derive(Debug, Clone, PartialEq)]
+pub enum FilterExpr {
+    And(Box<FilterExpr>, Box<FilterExpr>),
+    Or(Box<FilterExpr>, Box<FilterExpr>),
+    Not(Box<FilterExpr>),
+    Compare {
+        column: GoodsColumn,
+        op: FilterOp,
+        value: SortValue,
+    },
+    In {
+        column: GoodsColumn,
+        values: Vec<SortValue>,
+    },
+}
+
+// Comparison operators of filter expressions. Contains, StartsWith and EndsWith only
+// apply to text columns and ignore case, like the default text filters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Contains,
+    StartsWith,
+    EndsWith,
+}
+
+impl FilterOp {
+    fn text_match(&self) -> Option<TextMatch> {
+        let mode = match self {
+            FilterOp::Contains => MatchMode::Contains,
+            FilterOp::StartsWith => MatchMode::Prefix,
+            FilterOp::EndsWith => MatchMode::Suffix,
+            _ => return None,
+        };
+        Some(TextMatch { mode, case_sensitive: false })
+    }
+
+    fn sql(&self) -> &'static str {
+        match self {
+            FilterOp::Eq => " = ",
+            FilterOp::Ne => " <> ",
+            FilterOp::Lt => " < ",
+            FilterOp::Le => " <= ",
+            FilterOp::Gt => " > ",
+            FilterOp::Ge => " >= ",
+            // Pattern operators are compiled through push_text_match
+            FilterOp::Contains | FilterOp::StartsWith | FilterOp::EndsWith => " ILIKE ",
+        }
+    }
+}
+
+// How a text filter is matched against its column
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchMode {
+    Exact,
+    Prefix,
+    Contains,
+    Suffix,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextMatch {
+    pub mode: MatchMode,
+    pub case_sensitive: bool,
+}
+
+impl Default for TextMatch {
+    // Case-insensitive substring match, the historical behaviour of text filters
+    fn default() -> Self {
+        Self {
+            mode: MatchMode::Contains,
+            case_sensitive: false,
+        }
+    }
+}
+
+// Escape LIKE wildcards so user input only ever matches literally (used with ESCAPE '\')
+pub fn escape_like(input: &str) -> String {
+    let mut escaped = String::with_capacity(input.len());
+    for c in input.chars() {
+        if matches!(c, '\\' | '%' | '_') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct GoodsSearchParams {
     pub goods_id: Option<i32>,
     pub material_code: Option<String>,
     pub goods_name: Option<String>,
+    pub material_code_match: TextMatch,
+    pub goods_name_match: TextMatch,
     pub price: Option<rust_decimal::Decimal>,
     pub volumn_l: Option<rust_decimal::Decimal>,
     pub mass_g: Option<rust_decimal::Decimal>,
@@ -29,6 +302,192 @@ pub struct GoodsSearchParams {
     pub max_mass_g: Option<rust_decimal::Decimal>,
     pub min_price: Option<rust_decimal::Decimal>,
     pub max_price: Option<rust_decimal::Decimal>,
+    // Full-text query in websearch syntax ("organic -frozen", "\"green tea\"")
+    pub text_query: Option<String>,
+    // Description tags, combined according to tag_mode
+    pub tags: Vec<String>,
+    pub tag_mode: TagMode,
+    // Boolean expression from filter=, ANDed with the other filters
+    pub filter: Option<FilterExpr>,
+    // Breakdowns to compute alongside the results; they do not filter
+    pub facets: Vec<Facet>,
+    // Sparse fieldset: the columns each returned good is limited to; empty for all
+    pub fields: Vec<GoodsColumn>,
+    pub limit: i64,
+    pub offset: i64,
+    // Sort keys in priority order; goods_id ASC is always the final tie-breaker
+    pub sort: Vec<SortKey>,
+    // Keyset pagination: only return goods sorted after this position
+    pub after: Option<Keyset>,
+    pub fuzzy: FuzzyMode,
+    pub fuzzy_threshold: f32,
+}
+
+// A distinct goods_name or material_code starting with a typed prefix
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRow)]
+pub struct Suggestion {
+    pub value: String,
+    // Column the value comes from: "goods_name" or "material_code"
+    pub field: String,
+}
+
+// Breakdowns of a search's matches that `facets=` can ask for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Facet {
+    Price,
+    MassG,
+    VolumnL,
+    MassBase,
+    VolumnBase,
+    Tag,
+}
+
+// Most tags listed in a tag facet; the most used ones are kept
+const MAX_TAG_FACETS: usize = 50;
+
+impl Facet {
+    pub const ALL: [Facet; 6] = [
+        Facet::Price,
+        Facet::MassG,
+        Facet::VolumnL,
+        Facet::MassBase,
+        Facet::VolumnBase,
+        Facet::Tag,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Facet::Price => "price",
+            Facet::MassG => "mass_g",
+            Facet::VolumnL => "volumn_l",
+            Facet::MassBase => "mass_base",
+            Facet::VolumnBase => "volumn_base",
+            Facet::Tag => "tag",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|facet| facet.name() == name)
+    }
+
+    // Ascending bucket boundaries of the range facets; a bucket holds values from its
+    // lower boundary (inclusive) up to the next one (exclusive)
+    pub fn boundaries(&self) -> Vec<rust_decimal::Decimal> {
+        use rust_decimal::Decimal;
+        match self {
+            Facet::Price => [10, 50, 100, 500, 1000].map(Decimal::from).to_vec(),
+            Facet::MassG => [100, 250, 500, 1000, 5000].map(Decimal::from).to_vec(),
+            Facet::VolumnL => vec![
+                Decimal::new(25, 2),
+                Decimal::new(5, 1),
+                Decimal::from(1),
+                Decimal::from(2),
+                Decimal::from(5),
+            ],
+            Facet::MassBase | Facet::VolumnBase | Facet::Tag => Vec::new(),
+        }
+    }
+
+    // SQL computing the facet value of a row of `matched` (see GoodsTable::facets);
+    // range facets bind their boundaries as the next parameter
+    fn push_value(&self, query: &mut QueryBuilder<'_, Postgres>) {
+        match self {
+            Facet::Price | Facet::MassG | Facet::VolumnL => {
+                query.push(format!("width_bucket({}, ", self.name()));
+                query.push_bind(self.boundaries());
+                query.push("::numeric[])::text");
+            }
+            Facet::MassBase | Facet::VolumnBase => {
+                query.push(format!("{}::text", self.name()));
+            }
+            Facet::Tag => {
+                query.push("tag");
+            }
+        }
+    }
+}
+
+// One row of a facet: a range bucket (min inclusive, max exclusive, open-ended at either
+// end) or a single value, with the number of matching goods
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FacetCount {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min: Option<rust_decimal::Decimal>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max: Option<rust_decimal::Decimal>,
+    pub count: i64,
+}
+
+// Requested facets keyed by facet name
+pub type Facets = BTreeMap<String, Vec<FacetCount>>;
+
+// Numeric columns summarized by GoodsTable::stats
+pub const STATS_COLUMNS: [GoodsColumn; 3] = [GoodsColumn::Price, GoodsColumn::MassG, GoodsColumn::VolumnL];
+// Percentiles reported for each of them, as fractions
+const STATS_PERCENTILES: [f64; 7] = [0.1, 0.25, 0.5, 0.75, 0.9, 0.95, 0.99];
+
+// Summary of one numeric column over the goods matching a search. All values are None
+// when nothing matches. Percentiles are nearest-rank values keyed "p10", "p25", ...
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ColumnStats {
+    pub min: Option<rust_decimal::Decimal>,
+    pub max: Option<rust_decimal::Decimal>,
+    pub avg: Option<rust_decimal::Decimal>,
+    pub median: Option<rust_decimal::Decimal>,
+    pub sum: Option<rust_decimal::Decimal>,
+    pub percentiles: BTreeMap<String, rust_decimal::Decimal>,
+}
+
+// Number of matching goods plus a ColumnStats per column in STATS_COLUMNS, keyed by name
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GoodsStats {
+    pub count: i64,
+    #[serde(flatten)]
+    pub columns: BTreeMap<String, ColumnStats>,
+}
+
+// How `tag=` filters combine: goods carrying all of the tags, any of them, or none of them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TagMode {
+    #[default]
+    All,
+    Any,
+    None,
+}
+
+// A description tag and the number of goods carrying it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRow)]
+pub struct TagCount {
+    pub tag: String,
+    pub count: i64,
+}
+
+// When a search matches goods by trigram similarity instead of exactly
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FuzzyMode {
+    // Never match fuzzily
+    Off,
+    // Retry fuzzily only when the exact search matches nothing
+    #[default]
+    Auto,
+    // Always match fuzzily
+    Always,
+}
+
+// One page of search results together with the number of rows matching the filters
+#[derive(Debug, Clone)]
+pub struct GoodsPage {
+    pub goods: Vec<Good>,
+    pub total: i64,
+    pub has_more: bool,
+    // Sort the page was ordered by, without the goods_id tie-breaker
+    pub sort: Vec<SortKey>,
+    // Whether goods were matched by similarity, and whether that was a fallback
+    // from an exact search that found nothing
+    pub fuzzy: bool,
+    pub fell_back_to_fuzzy: bool,
 }
 
 impl GoodsSearchParams {
@@ -37,6 +496,8 @@ impl GoodsSearchParams {
             goods_id: None,
             material_code: None,
             goods_name: None,
+            material_code_match: TextMatch::default(),
+            goods_name_match: TextMatch::default(),
             price: None,
             volumn_l: None,
             mass_g: None,
@@ -46,170 +507,900 @@ impl GoodsSearchParams {
             max_mass_g: None,
             min_price: None,
             max_price: None,
+            text_query: None,
+            tags: Vec::new(),
+            tag_mode: TagMode::default(),
+            filter: None,
+            facets: Vec::new(),
+            fields: Vec::new(),
+            limit: DEFAULT_PAGE_SIZE,
+            offset: 0,
+            sort: Vec::new(),
+            after: None,
+            fuzzy: FuzzyMode::default(),
+            fuzzy_threshold: DEFAULT_FUZZY_THRESHOLD,
+        }
+    }
+
+    // goods_name=* and material_code=* match every good: they list the catalog without a
+    // condition on that column, while every other filter still applies
+    fn goods_name_filter(&self) -> Option<&str> {
+        self.goods_name.as_deref().filter(|goods_name| *goods_name != "*")
+    }
+
+    fn material_code_filter(&self) -> Option<&str> {
+        self.material_code.as_deref().filter(|material_code| *material_code != "*")
+    }
+
+    // Text a fuzzy search matches by similarity, each with the columns it is compared to:
+    // q against the name and material code, goods_name and material_code against their
+    // own column. Filters with an exact match mode are identifiers and stay exact.
+    pub fn fuzzy_terms(&self) -> Vec<(&str, &'static [&'static str])> {
+        let mut terms: Vec<(&str, &'static [&'static str])> = Vec::new();
+        if let Some(text_query) = &self.text_query {
+            terms.push((text_query, &["goods_name", "material_code"]));
+        }
+        if let Some(goods_name) = self.goods_name_filter()
+            && self.goods_name_match.mode != MatchMode::Exact
+        {
+            terms.push((goods_name, &["goods_name"]));
         }
+        if let Some(material_code) = self.material_code_filter()
+            && self.material_code_match.mode != MatchMode::Exact
+        {
+            terms.push((material_code, &["material_code"]));
+        }
+        terms
     }
 
-    pub fn is_get_all(&self) -> bool {
-        matches!(self.goods_name.as_deref(), Some("*"))
-            || matches!(self.material_code.as_deref(), Some("*"))
+    pub fn is_fuzzy(&self) -> bool {
+        self.fuzzy == FuzzyMode::Always && !self.fuzzy_terms().is_empty()
+    }
+
+    // Requested sort, or best match first for full-text and fuzzy searches
+    pub fn resolved_sort(&self) -> Vec<SortKey> {
+        if self.sort.is_empty() && (self.text_query.is_some() || self.is_fuzzy()) {
+            return vec![SortKey { column: GoodsColumn::Relevance, descending: true }];
+        }
+        self.sort.clone()
+    }
+
+    // Key under which the page this search returns is cached, built field by field so a new
+    // search parameter does not compile until it is added here. Searches that read the same
+    // rows share a key: tags and fields are sets, numbers ignore trailing zeros, and facets
+    // are counted separately from the page.
+    pub fn cache_key(&self) -> String {
+        let GoodsSearchParams {
+            goods_id,
+            material_code,
+            goods_name,
+            material_code_match,
+            goods_name_match,
+            price,
+            volumn_l,
+            mass_g,
+            min_volumn_l,
+            max_volumn_l,
+            min_mass_g,
+            max_mass_g,
+            min_price,
+            max_price,
+            text_query,
+            tags,
+            tag_mode,
+            filter,
+            facets: _,
+            fields,
+            limit,
+            offset,
+            sort,
+            after,
+            fuzzy,
+            fuzzy_threshold,
+        } = self;
+
+        let decimal = |value: &Option<rust_decimal::Decimal>| value.map(|value| value.normalize().to_string());
+        let text_match = |text_match: &TextMatch| {
+            let mode = match text_match.mode {
+                MatchMode::Exact => "exact",
+                MatchMode::Prefix => "prefix",
+                MatchMode::Contains => "contains",
+                MatchMode::Suffix => "suffix",
+            };
+            serde_json::json!([mode, text_match.case_sensitive])
+        };
+        let tags: std::collections::BTreeSet<&String> = tags.iter().collect();
+        let fields: std::collections::BTreeSet<&str> = fields.iter().map(GoodsColumn::name).collect();
+        let sort: Vec<String> = sort
+            .iter()
+            .map(|key| format!("{}{}", if key.descending { "-" } else { "" }, key.column.name()))
+            .collect();
+        let tag_mode = match tag_mode {
+            TagMode::All => "all",
+            TagMode::Any => "any",
+            TagMode::None => "none",
+        };
+        let fuzzy = match fuzzy {
+            FuzzyMode::Off => "off",
+            FuzzyMode::Auto => "auto",
+            FuzzyMode::Always => "always",
+        };
+
+        serde_json::json!({
+            "goods_id": goods_id,
+            "material_code": [material_code, text_match(material_code_match)],
+            "goods_name": [goods_name, text_match(goods_name_match)],
+            "price": decimal(price),
+            "volumn_l": decimal(volumn_l),
+            "mass_g": decimal(mass_g),
+            "min_volumn_l": decimal(min_volumn_l),
+            "max_volumn_l": decimal(max_volumn_l),
+            "min_mass_g": decimal(min_mass_g),
+            "max_mass_g": decimal(max_mass_g),
+            "min_price": decimal(min_price),
+            "max_price": decimal(max_price),
+            "q": text_query,
+            "tags": [tags, tag_mode],
+            "filter": filter.as_ref().map(FilterExpr::cache_key),
+            "fields": fields,
+            "limit": limit,
+            "offset": offset,
+            "sort": sort,
+            "after": after.as_ref().map(|after| serde_json::json!([after.values, after.goods_id])),
+            "fuzzy": [fuzzy, fuzzy_threshold],
+        })
+        .to_string()
+    }
+}
+
+impl FilterExpr {
+    // Part of GoodsSearchParams::cache_key, using the operator names of filter=
+    fn cache_key(&self) -> serde_json::Value {
+        match self {
+            FilterExpr::And(left, right) => serde_json::json!(["and", left.cache_key(), right.cache_key()]),
+            FilterExpr::Or(left, right) => serde_json::json!(["or", left.cache_key(), right.cache_key()]),
+            FilterExpr::Not(expr) => serde_json::json!(["not", expr.cache_key()]),
+            FilterExpr::Compare { column, op, value } => {
+                let op = match op {
+                    FilterOp::Eq => "eq",
+                    FilterOp::Ne => "ne",
+                    FilterOp::Lt => "lt",
+                    FilterOp::Le => "le",
+                    FilterOp::Gt => "gt",
+                    FilterOp::Ge => "ge",
+                    FilterOp::Contains => "co",
+                    FilterOp::StartsWith => "sw",
+                    FilterOp::EndsWith => "ew",
+                };
+                serde_json::json!([op, column.name(), value])
+            }
+            FilterExpr::In { column, values } => serde_json::json!(["in", column.name(), values]),
+        }
+    }
+}
+
+impl GoodsPage {
+    // Rough number of bytes the page holds, used to bound the search cache
+    pub fn approximate_size(&self) -> usize {
+        let text: usize = self
+            .goods
+            .iter()
+            .map(|good| {
+                let description = good.description.iter().flatten();
+                good.material_code.len()
+                    + good.goods_name.len()
+                    + description.map(|tag| tag.len() + std::mem::size_of::<String>()).sum::<usize>()
+            })
+            .sum();
+        std::mem::size_of::<Self>() + self.goods.len() * std::mem::size_of::<Good>() + text
     }
 }
 
 #[derive(Clone)]
 pub struct GoodsTable {
     pool: PgPool,
+    // Privileged pool for writes; None when no writer is configured
+    write_pool: Option<PgPool>,
+    // Whether pg_trgm is installed, which fuzzy matching needs
+    fuzzy_matching: bool,
 }
 
 impl GoodsTable {
-    pub fn new(pool: PgPool) -> Self {
-        Self { pool }
+    pub fn new(pool: PgPool, write_pool: Option<PgPool>) -> Self {
+        Self { pool, write_pool, fuzzy_matching: true }
+    }
+
+    pub fn without_fuzzy_matching(self) -> Self {
+        Self { fuzzy_matching: false, ..self }
+    }
+
+    pub fn can_write(&self) -> bool {
+        self.write_pool.is_some()
+    }
+
+    pub fn fuzzy_matching(&self) -> bool {
+        self.fuzzy_matching
+    }
+
+    // Fuzzy matching uses word_similarity from pg_trgm (see db/setup.sql)
+    pub async fn has_trigram_extension(&self) -> Result<bool, sqlx::Error> {
+        sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM pg_extension WHERE extname = 'pg_trgm')")
+            .fetch_one(&self.pool)
+            .await
+    }
+
+    fn writer(&self) -> Result<&PgPool, sqlx::Error> {
+        self.write_pool
+            .as_ref()
+            .ok_or_else(|| sqlx::Error::Configuration("no database writer configured".into()))
     }
 
-    pub async fn search(&self, params: GoodsSearchParams) -> Result<Vec<Good>, sqlx::Error> {
-        // Handle get all case
-        if params.is_get_all() {
-            return self.get_all().await;
+    pub async fn search(&self, params: GoodsSearchParams) -> Result<GoodsPage, sqlx::Error> {
+        let page = self.search_page(&params).await?;
+
+        // A misspelled name should not be a dead end: retry by similarity
+        if page.total == 0 && params.fuzzy == FuzzyMode::Auto && self.fuzzy_matching && !params.fuzzy_terms().is_empty() {
+            let fuzzy_params = GoodsSearchParams { fuzzy: FuzzyMode::Always, ..params };
+            match self.search_page(&fuzzy_params).await {
+                Ok(fuzzy_page) => return Ok(GoodsPage { fell_back_to_fuzzy: true, ..fuzzy_page }),
+                // undefined_function: pg_trgm was dropped since startup, so there is no fallback
+                Err(sqlx::Error::Database(e)) if e.code().as_deref() == Some("42883") => {
+                    warn!("Fuzzy fallback unavailable, is pg_trgm installed? {}", e);
+                }
+                Err(e) => return Err(e),
+            }
         }
 
-        // Build dynamic query with parameterized statements to prevent SQL injection
-        let mut query = "SELECT goods_id, material_code, goods_name, description, price, volumn_l, mass_g, mass_base, volumn_base FROM goods WHERE 1=1".to_string();
-        let mut bind_count = 0;
-        let mut conditions = Vec::new();
+        Ok(page)
+    }
+
+    async fn search_page(&self, params: &GoodsSearchParams) -> Result<GoodsPage, sqlx::Error> {
+        let mut query = Self::search_query(params);
+        let mut goods = query.build_query_as::<Good>().fetch_all(&self.pool).await?;
+        let has_more = goods.len() as i64 > params.limit;
+        goods.truncate(params.limit as usize);
+
+        // The total ignores the cursor so it stays the size of the whole result set
+        let total = self.count(params).await?;
+
+        Ok(GoodsPage {
+            goods,
+            total,
+            has_more,
+            sort: params.resolved_sort(),
+            fuzzy: params.is_fuzzy(),
+            fell_back_to_fuzzy: false,
+        })
+    }
+
+    // Build dynamic query with parameterized statements to prevent SQL injection
+    fn search_query(params: &GoodsSearchParams) -> QueryBuilder<'static, Postgres> {
+        let mut query = Self::ordered_query(params);
+        // Fetch one extra row to know whether another page follows
+        query.push(" LIMIT ").push_bind(params.limit + 1);
+        query.push(" OFFSET ").push_bind(params.offset);
+        query
+    }
 
-        // Build WHERE conditions safely using parameterized queries
-        if params.goods_id.is_some() {
-            bind_count += 1;
-            conditions.push(format!(" AND goods_id = ${}", bind_count));
+    // Every good matching the search filters, in sort order and without pagination. Rows
+    // are read from the database as the stream is polled, so even a full export of the
+    // catalog is never held in memory.
+    pub fn export(&self, params: GoodsSearchParams) -> impl Stream<Item = Result<Good, sqlx::Error>> + Send + 'static {
+        let pool = self.pool.clone();
+        async_stream::try_stream! {
+            let mut query = Self::ordered_query(&params);
+            let mut rows = query.build_query_as::<Good>().fetch(&pool);
+            while let Some(good) = rows.try_next().await? {
+                yield good;
+            }
         }
+    }
 
-        if params.material_code.is_some() && !params.is_get_all() {
-            bind_count += 1;
-            conditions.push(format!(" AND material_code ILIKE ${}", bind_count));
+    // SQL text of an export, with values left as $n placeholders
+    #[cfg(test)]
+    pub fn export_sql(params: &GoodsSearchParams) -> String {
+        Self::ordered_query(params).into_sql()
+    }
+
+    // Filtered and sorted goods; searches add the page bounds
+    fn ordered_query(params: &GoodsSearchParams) -> QueryBuilder<'static, Postgres> {
+        // The inner select filters and computes relevance; the outer one can then sort
+        // and apply the keyset on relevance like on any other column
+        let mut query = QueryBuilder::<Postgres>::new(format!("SELECT * FROM (SELECT {}", Self::selected_columns(params)));
+        if params.is_fuzzy() {
+            query.push(", ");
+            Self::push_fuzzy_score(&mut query, params);
+            query.push(" AS relevance");
+        } else if let Some(text_query) = &params.text_query {
+            query.push(format!(", ts_rank({}, websearch_to_tsquery('{}', ", search_document(), TEXT_SEARCH_CONFIG));
+            query.push_bind(text_query.clone());
+            query.push(")) AS relevance");
+        }
+        query.push(" FROM goods");
+        Self::push_conditions(&mut query, params);
+        query.push(") AS goods WHERE 1=1");
+        let sort = Self::effective_sort(&params.resolved_sort());
+        if let Some(after) = &params.after {
+            Self::push_keyset(&mut query, &sort, after);
         }
+        query.push(" ORDER BY ");
+        for (i, key) in sort.iter().enumerate() {
+            if i > 0 {
+                query.push(", ");
+            }
+            query.push(key.column.sort_expression());
+            query.push(if key.descending { " DESC" } else { " ASC" });
+        }
+        query
+    }
 
-        if params.goods_name.is_some() && !params.is_get_all() {
-            bind_count += 1;
-            conditions.push(format!(" AND goods_name ILIKE ${}", bind_count));
+    // Columns a search reads: all of them, or for a sparse fieldset the requested ones plus
+    // those the sort needs to order the rows and encode the next cursor
+    fn selected_columns(params: &GoodsSearchParams) -> String {
+        if params.fields.is_empty() {
+            return GOODS_COLUMNS.to_string();
         }
+        let sort = Self::effective_sort(&params.resolved_sort());
+        GoodsColumn::ALL
+            .iter()
+            .filter(|column| params.fields.contains(column) || sort.iter().any(|key| key.column == **column))
+            .map(|column| column.name())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
 
-        if params.price.is_some() {
-            bind_count += 1;
-            conditions.push(format!(" AND price = ${}", bind_count));
+    // SQL text of a search, with values left as $n placeholders
+    #[cfg(test)]
+    pub fn search_sql(params: &GoodsSearchParams) -> String {
+        Self::search_query(params).into_sql()
+    }
+
+    // Counts of the goods matching the search filters for each requested facet, all
+    // computed by a single query. Range facets list every bucket, including empty ones.
+    pub async fn facets(&self, params: &GoodsSearchParams) -> Result<Facets, sqlx::Error> {
+        if params.facets.is_empty() {
+            return Ok(Facets::new());
         }
 
-        if params.volumn_l.is_some() {
-            bind_count += 1;
-            conditions.push(format!(" AND volumn_l = ${}", bind_count));
+        let rows = Self::facets_query(params)
+            .build_query_as::<(String, String, i64)>()
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(Self::collect_facets(&params.facets, &rows))
+    }
+
+    // Group (facet, value, count) rows into per-facet counts
+    pub fn collect_facets(requested: &[Facet], rows: &[(String, String, i64)]) -> Facets {
+        let mut facets = Facets::new();
+        for facet in requested {
+            let rows: Vec<(&str, i64)> = rows
+                .iter()
+                .filter(|(name, _, _)| name == facet.name())
+                .map(|(_, value, count)| (value.as_str(), *count))
+                .collect();
+
+            let counts = match facet {
+                Facet::Price | Facet::MassG | Facet::VolumnL => {
+                    // width_bucket numbers the buckets from 0 (below the first boundary) to n
+                    let boundaries = facet.boundaries();
+                    (0..=boundaries.len())
+                        .map(|bucket| FacetCount {
+                            value: None,
+                            min: bucket.checked_sub(1).map(|i| boundaries[i]),
+                            max: boundaries.get(bucket).copied(),
+                            count: rows
+                                .iter()
+                                .find(|(value, _)| value.parse() == Ok(bucket))
+                                .map_or(0, |(_, count)| *count),
+                        })
+                        .collect()
+                }
+                Facet::MassBase | Facet::VolumnBase | Facet::Tag => {
+                    let mut counts: Vec<FacetCount> = rows
+                        .iter()
+                        .map(|(value, count)| FacetCount {
+                            value: Some(value.to_string()),
+                            min: None,
+                            max: None,
+                            count: *count,
+                        })
+                        .collect();
+                    if *facet == Facet::Tag {
+                        counts.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.value.cmp(&b.value)));
+                        counts.truncate(MAX_TAG_FACETS);
+                    } else {
+                        counts.sort_by_key(|count| count.value.as_deref().and_then(|v| v.parse::<i64>().ok()));
+                    }
+                    counts
+                }
+            };
+            facets.insert(facet.name().to_string(), counts);
         }
+        facets
+    }
 
-        if params.mass_g.is_some() {
-            bind_count += 1;
-            conditions.push(format!(" AND mass_g = ${}", bind_count));
+    // (facet, value, count) rows for every requested facet over the goods matching the filters
+    fn facets_query(params: &GoodsSearchParams) -> QueryBuilder<'static, Postgres> {
+        let mut query = QueryBuilder::<Postgres>::new(
+            "WITH matched AS (SELECT goods_id, price, mass_g, volumn_l, mass_base, volumn_base, description FROM goods",
+        );
+        Self::push_conditions(&mut query, params);
+        query.push(") ");
+        for (i, facet) in params.facets.iter().enumerate() {
+            if i > 0 {
+                query.push(" UNION ALL ");
+            }
+            query.push(format!("SELECT '{}'::text AS facet, ", facet.name()));
+            facet.push_value(&mut query);
+            // Goods carrying a tag, counted once however often it is repeated, like tag_counts
+            if *facet == Facet::Tag {
+                query.push(" AS value, COUNT(DISTINCT goods_id) AS count FROM matched");
+                query.push(" CROSS JOIN LATERAL unnest(description) AS tag");
+            } else {
+                query.push(" AS value, COUNT(*) AS count FROM matched");
+            }
+            query.push(" GROUP BY 2");
         }
+        query
+    }
+
+    #[cfg(test)]
+    pub fn facets_sql(params: &GoodsSearchParams) -> String {
+        Self::facets_query(params).into_sql()
+    }
 
-        if params.min_volumn_l.is_some() {
-            bind_count += 1;
-            conditions.push(format!(" AND volumn_l >= ${}", bind_count));
+    // Count, min/max/avg/median/sum and percentiles of the numeric columns over the goods
+    // matching the search filters, in one aggregate query
+    pub async fn stats(&self, params: &GoodsSearchParams) -> Result<GoodsStats, sqlx::Error> {
+        let row = Self::stats_query(params).build().fetch_one(&self.pool).await?;
+
+        let mut stats = GoodsStats {
+            count: row.try_get("count")?,
+            columns: BTreeMap::new(),
+        };
+        for column in STATS_COLUMNS {
+            let name = column.name();
+            let get = |stat: &str| row.try_get::<Option<rust_decimal::Decimal>, _>(format!("{}_{}", name, stat).as_str());
+            let percentiles: Option<Vec<rust_decimal::Decimal>> = row.try_get(format!("{}_percentiles", name).as_str())?;
+            stats.columns.insert(
+                name.to_string(),
+                ColumnStats {
+                    min: get("min")?,
+                    max: get("max")?,
+                    avg: get("avg")?,
+                    median: get("median")?,
+                    sum: get("sum")?,
+                    percentiles: STATS_PERCENTILES
+                        .iter()
+                        .map(|p| format!("p{}", (p * 100.0).round()))
+                        .zip(percentiles.unwrap_or_default())
+                        .collect(),
+                },
+            );
         }
 
-        if params.max_volumn_l.is_some() {
-            bind_count += 1;
-            conditions.push(format!(" AND volumn_l <= ${}", bind_count));
+        Ok(stats)
+    }
+
+    fn stats_query(params: &GoodsSearchParams) -> QueryBuilder<'static, Postgres> {
+        let mut query = QueryBuilder::<Postgres>::new("SELECT COUNT(*) AS count");
+        for column in STATS_COLUMNS {
+            let name = column.name();
+            query.push(format!(
+                ", MIN({name}) AS {name}_min, MAX({name}) AS {name}_max, \
+                ROUND(AVG({name}), 4) AS {name}_avg, SUM({name}) AS {name}_sum, \
+                ROUND((percentile_cont(0.5) WITHIN GROUP (ORDER BY {name}))::numeric, 4) AS {name}_median, \
+                percentile_disc("
+            ));
+            query.push_bind(STATS_PERCENTILES.to_vec());
+            query.push(format!("::float8[]) WITHIN GROUP (ORDER BY {name}) AS {name}_percentiles"));
         }
+        query.push(" FROM goods");
+        Self::push_conditions(&mut query, params);
+        query
+    }
+
+    #[cfg(test)]
+    pub fn stats_sql(params: &GoodsSearchParams) -> String {
+        Self::stats_query(params).into_sql()
+    }
 
-        if params.min_mass_g.is_some() {
-            bind_count += 1;
-            conditions.push(format!(" AND mass_g >= ${}", bind_count));
+    // Distinct names and material codes starting with `prefix` (ignoring case), shortest
+    // first. lower(column) LIKE can use the text_pattern_ops indexes from db/setup.sql.
+    // Postgres cancels the lookup once `timeout` is spent, so a slow one does not keep
+    // running after the client has been answered.
+    pub async fn suggest(&self, prefix: &str, limit: i64, timeout: Duration) -> Result<Vec<Suggestion>, sqlx::Error> {
+        let pattern = format!("{}%", escape_like(prefix));
+        let mut transaction = self.pool.begin().await?;
+        // SET LOCAL, with the value bound: it only lasts until the end of the transaction
+        sqlx::query("SELECT set_config('statement_timeout', $1, true)")
+            .bind(format!("{}ms", timeout.as_millis()))
+            .execute(&mut *transaction)
+            .await?;
+        let suggestions = sqlx::query_as::<_, Suggestion>(
+            "SELECT value, field FROM ( \
+                (SELECT goods_name AS value, 'goods_name' AS field FROM goods \
+                    WHERE lower(goods_name) LIKE lower($1) ESCAPE '\\' \
+                    GROUP BY goods_name ORDER BY length(goods_name), goods_name LIMIT $2) \
+                UNION ALL \
+                (SELECT material_code, 'material_code' FROM goods \
+                    WHERE lower(material_code) LIKE lower($1) ESCAPE '\\' \
+                    GROUP BY material_code ORDER BY length(material_code), material_code LIMIT $2) \
+            ) AS suggestions ORDER BY length(value), value, field LIMIT $2",
+        )
+        .bind(pattern)
+        .bind(limit)
+        .fetch_all(&mut *transaction)
+        .await?;
+        transaction.commit().await?;
+        Ok(suggestions)
+    }
+
+    // Every distinct description tag with the number of goods carrying it, most used first
+    pub async fn tag_counts(&self) -> Result<Vec<TagCount>, sqlx::Error> {
+        sqlx::query_as::<_, TagCount>(
+            "SELECT tag, COUNT(DISTINCT goods_id) AS count \
+            FROM goods CROSS JOIN LATERAL unnest(description) AS tag \
+            GROUP BY tag ORDER BY count DESC, tag",
+        )
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    pub async fn find_by_id(&self, goods_id: i32) -> Result<Option<Good>, sqlx::Error> {
+        sqlx::query_as::<_, Good>(&format!("SELECT {} FROM goods WHERE goods_id = $1", GOODS_COLUMNS))
+            .bind(goods_id)
+            .fetch_optional(&self.pool)
+            .await
+    }
+
+    // Exact, case-sensitive match: material codes are identifiers, not search terms
+    pub async fn find_by_material_code(&self, material_code: &str) -> Result<Option<Good>, sqlx::Error> {
+        sqlx::query_as::<_, Good>(&format!(
+            "SELECT {} FROM goods WHERE material_code = $1 ORDER BY goods_id ASC LIMIT 1",
+            GOODS_COLUMNS
+        ))
+        .bind(material_code)
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    // Batch lookup: every good whose id or exact material code is in the given lists
+    pub async fn find_many(&self, goods_ids: &[i32], material_codes: &[String]) -> Result<Vec<Good>, sqlx::Error> {
+        sqlx::query_as::<_, Good>(&format!(
+            "SELECT {} FROM goods WHERE goods_id = ANY($1) OR material_code = ANY($2) ORDER BY goods_id ASC",
+            GOODS_COLUMNS
+        ))
+        .bind(goods_ids)
+        .bind(material_codes)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    pub async fn insert(&self, good: NewGood) -> Result<Good, sqlx::Error> {
+        sqlx::query_as::<_, Good>(&format!(
+            "INSERT INTO goods (material_code, goods_name, description, price, volumn_l, mass_g, mass_base, volumn_base) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8) RETURNING {}",
+            GOODS_COLUMNS
+        ))
+        .bind(good.material_code)
+        .bind(good.goods_name)
+        .bind(good.description)
+        .bind(good.price)
+        .bind(good.volumn_l)
+        .bind(good.mass_g)
+        .bind(good.mass_base)
+        .bind(good.volumn_base)
+        .fetch_one(self.writer()?)
+        .await
+    }
+
+    // Replace every column of an existing good; None when the good does not exist
+    pub async fn update(&self, goods_id: i32, good: NewGood) -> Result<Option<Good>, sqlx::Error> {
+        sqlx::query_as::<_, Good>(&format!(
+            "UPDATE goods SET material_code = $1, goods_name = $2, description = $3, price = $4, \
+             volumn_l = $5, mass_g = $6, mass_base = $7, volumn_base = $8 WHERE goods_id = $9 RETURNING {}",
+            GOODS_COLUMNS
+        ))
+        .bind(good.material_code)
+        .bind(good.goods_name)
+        .bind(good.description)
+        .bind(good.price)
+        .bind(good.volumn_l)
+        .bind(good.mass_g)
+        .bind(good.mass_base)
+        .bind(good.volumn_base)
+        .bind(goods_id)
+        .fetch_optional(self.writer()?)
+        .await
+    }
+
+    // Update only the columns present in the patch; None when the good does not exist
+    pub async fn patch(&self, goods_id: i32, patch: GoodPatch) -> Result<Option<Good>, sqlx::Error> {
+        if patch.is_empty() {
+            return self.find_by_id(goods_id).await;
         }
 
-        if params.max_mass_g.is_some() {
-            bind_count += 1;
-            conditions.push(format!(" AND mass_g <= ${}", bind_count));
+        let mut query = QueryBuilder::<Postgres>::new("UPDATE goods SET ");
+        let mut set = query.separated(", ");
+        if let Some(material_code) = patch.material_code {
+            set.push("material_code = ").push_bind_unseparated(material_code);
+        }
+        if let Some(goods_name) = patch.goods_name {
+            set.push("goods_name = ").push_bind_unseparated(goods_name);
         }
+        if let Some(description) = patch.description {
+            set.push("description = ").push_bind_unseparated(description);
+        }
+        if let Some(price) = patch.price {
+            set.push("price = ").push_bind_unseparated(price);
+        }
+        if let Some(volumn_l) = patch.volumn_l {
+            set.push("volumn_l = ").push_bind_unseparated(volumn_l);
+        }
+        if let Some(mass_g) = patch.mass_g {
+            set.push("mass_g = ").push_bind_unseparated(mass_g);
+        }
+        if let Some(mass_base) = patch.mass_base {
+            set.push("mass_base = ").push_bind_unseparated(mass_base);
+        }
+        if let Some(volumn_base) = patch.volumn_base {
+            set.push("volumn_base = ").push_bind_unseparated(volumn_base);
+        }
+        query.push(" WHERE goods_id = ").push_bind(goods_id);
+        query.push(format!(" RETURNING {}", GOODS_COLUMNS));
+
+        query.build_query_as::<Good>().fetch_optional(self.writer()?).await
+    }
+
+    // Delete a good and return it; None when the good does not exist
+    pub async fn delete(&self, goods_id: i32) -> Result<Option<Good>, sqlx::Error> {
+        sqlx::query_as::<_, Good>(&format!("DELETE FROM goods WHERE goods_id = $1 RETURNING {}", GOODS_COLUMNS))
+            .bind(goods_id)
+            .fetch_optional(self.writer()?)
+            .await
+    }
+
+    pub async fn count(&self, params: &GoodsSearchParams) -> Result<i64, sqlx::Error> {
+        let mut query = QueryBuilder::<Postgres>::new("SELECT COUNT(*) FROM goods");
+        Self::push_conditions(&mut query, params);
+
+        query.build_query_scalar::<i64>().fetch_one(&self.pool).await
+    }
+
+    fn push_text_match(query: &mut QueryBuilder<'_, Postgres>, column: &'static str, value: &str, text_match: TextMatch) {
+        query.push(column);
+
+        if text_match.mode == MatchMode::Exact && text_match.case_sensitive {
+            query.push(" = ").push_bind(value.to_string());
+            return;
+        }
+
+        let escaped = escape_like(value);
+        let pattern = match text_match.mode {
+            MatchMode::Exact => escaped,
+            MatchMode::Prefix => format!("{}%", escaped),
+            MatchMode::Contains => format!("%{}%", escaped),
+            MatchMode::Suffix => format!("%{}", escaped),
+        };
+        query.push(if text_match.case_sensitive { " LIKE " } else { " ILIKE " });
+        query.push_bind(pattern);
+        query.push(" ESCAPE '\\'");
+    }
+
+    // Requested sort keys followed by goods_id ASC, which makes the order total
+    fn effective_sort(sort: &[SortKey]) -> Vec<SortKey> {
+        let mut keys = sort.to_vec();
+        if !keys.iter().any(|key| key.column == GoodsColumn::GoodsId) {
+            keys.push(SortKey { column: GoodsColumn::GoodsId, descending: false });
+        }
+        keys
+    }
 
-        if params.min_price.is_some() {
-            bind_count += 1;
-            conditions.push(format!(" AND price >= ${}", bind_count));
+    // Append the keyset condition selecting rows that sort strictly after `after`:
+    // (k1 > v1) OR (k1 = v1 AND k2 > v2) OR ..., with < for descending keys
+    fn push_keyset(query: &mut QueryBuilder<'_, Postgres>, sort: &[SortKey], after: &Keyset) {
+        let mut values = after.values.clone();
+        if values.len() < sort.len() {
+            values.push(SortValue::Integer(after.goods_id.into()));
         }
 
-        if params.max_price.is_some() {
-            bind_count += 1;
-            conditions.push(format!(" AND price <= ${}", bind_count));
+        query.push(" AND (");
+        for i in 0..sort.len() {
+            if i > 0 {
+                query.push(" OR ");
+            }
+            query.push("(");
+            for (key, value) in sort.iter().zip(&values).take(i) {
+                query.push(key.column.sort_expression()).push(" = ");
+                Self::push_sort_value(query, value);
+                query.push(" AND ");
+            }
+            query.push(sort[i].column.sort_expression());
+            query.push(if sort[i].descending { " < " } else { " > " });
+            Self::push_sort_value(query, &values[i]);
+            query.push(")");
         }
+        query.push(")");
+    }
 
-        // Append conditions to query
-        query.push_str(&conditions.join(""));
-        query.push_str(" ORDER BY goods_id ASC");
+    fn push_sort_value(query: &mut QueryBuilder<'_, Postgres>, value: &SortValue) {
+        match value {
+            SortValue::Integer(v) => query.push_bind(*v),
+            SortValue::Float(v) => query.push_bind(*v),
+            SortValue::Decimal(v) => query.push_bind(*v),
+            SortValue::Text(v) => query.push_bind(v.clone()),
+            SortValue::TextArray(v) => query.push_bind(v.clone()),
+        };
+    }
 
-        // Build and execute query with proper parameter binding
-        let mut sql_query = sqlx::query_as::<_, Good>(&query);
+    // Append the WHERE clause for the search filters, binding every value as a parameter
+    fn push_conditions(query: &mut QueryBuilder<'_, Postgres>, params: &GoodsSearchParams) {
+        query.push(" WHERE 1=1");
 
-        // Bind parameters in the same order as they were added
         if let Some(goods_id) = params.goods_id {
-            sql_query = sql_query.bind(goods_id);
+            query.push(" AND goods_id = ").push_bind(goods_id);
         }
 
-        if let Some(material_code) = params.material_code {
-            if material_code != "*" {
-                sql_query = sql_query.bind(format!("%{}%", material_code));
-            }
+        // A fuzzy search replaces the text conditions it matches by similarity with a
+        // threshold on the score
+        let fuzzy = params.is_fuzzy();
+        let matched_fuzzily = |text_match: TextMatch| fuzzy && text_match.mode != MatchMode::Exact;
+        if fuzzy {
+            query.push(" AND ");
+            Self::push_fuzzy_score(query, params);
+            query.push(" >= ").push_bind(params.fuzzy_threshold);
         }
 
-        if let Some(goods_name) = params.goods_name {
-            if goods_name != "*" {
-                sql_query = sql_query.bind(format!("%{}%", goods_name));
-            }
+        if let Some(material_code) = params.material_code_filter()
+            && !matched_fuzzily(params.material_code_match)
+        {
+            query.push(" AND ");
+            Self::push_text_match(query, "material_code", material_code, params.material_code_match);
+        }
+
+        if let Some(goods_name) = params.goods_name_filter()
+            && !matched_fuzzily(params.goods_name_match)
+        {
+            query.push(" AND ");
+            Self::push_text_match(query, "goods_name", goods_name, params.goods_name_match);
         }
 
         if let Some(price) = params.price {
-            sql_query = sql_query.bind(price);
+            query.push(" AND price = ").push_bind(price);
         }
 
         if let Some(volumn_l) = params.volumn_l {
-            sql_query = sql_query.bind(volumn_l);
+            query.push(" AND volumn_l = ").push_bind(volumn_l);
         }
 
         if let Some(mass_g) = params.mass_g {
-            sql_query = sql_query.bind(mass_g);
+            query.push(" AND mass_g = ").push_bind(mass_g);
         }
 
         if let Some(min_volumn_l) = params.min_volumn_l {
-            sql_query = sql_query.bind(min_volumn_l);
+            query.push(" AND volumn_l >= ").push_bind(min_volumn_l);
         }
 
         if let Some(max_volumn_l) = params.max_volumn_l {
-            sql_query = sql_query.bind(max_volumn_l);
+            query.push(" AND volumn_l <= ").push_bind(max_volumn_l);
         }
 
         if let Some(min_mass_g) = params.min_mass_g {
-            sql_query = sql_query.bind(min_mass_g);
+            query.push(" AND mass_g >= ").push_bind(min_mass_g);
         }
 
         if let Some(max_mass_g) = params.max_mass_g {
-            sql_query = sql_query.bind(max_mass_g);
+            query.push(" AND mass_g <= ").push_bind(max_mass_g);
         }
 
         if let Some(min_price) = params.min_price {
-            sql_query = sql_query.bind(min_price);
+            query.push(" AND price >= ").push_bind(min_price);
         }
 
         if let Some(max_price) = params.max_price {
-            sql_query = sql_query.bind(max_price);
+            query.push(" AND price <= ").push_bind(max_price);
+        }
+
+        if !params.tags.is_empty() {
+            // A good without a description carries no tags
+            query.push(match params.tag_mode {
+                TagMode::All => " AND description @> ",
+                TagMode::Any => " AND description && ",
+                TagMode::None => " AND NOT (COALESCE(description, '{}') && ",
+            });
+            query.push_bind(params.tags.clone());
+            if params.tag_mode == TagMode::None {
+                query.push(")");
+            }
         }
 
-        sql_query.fetch_all(&self.pool).await
+        if let Some(filter) = &params.filter {
+            query.push(" AND ");
+            Self::push_filter(query, filter);
+        }
+
+        if let Some(text_query) = &params.text_query
+            && !fuzzy
+        {
+            query.push(format!(" AND {} @@ websearch_to_tsquery('{}', ", search_document(), TEXT_SEARCH_CONFIG));
+            query.push_bind(text_query.clone());
+            query.push(")");
+        }
     }
 
-    async fn get_all(&self) -> Result<Vec<Good>, sqlx::Error> {
-        sqlx::query_as::<_, Good>(
-            "SELECT goods_id, material_code, goods_name, description, price, volumn_l, mass_g, mass_base, volumn_base FROM goods ORDER BY goods_id ASC"
-        )
-        .fetch_all(&self.pool)
-        .await
+    // Compile a filter expression, binding every value as a parameter. Column names come
+    // from GoodsColumn, so nothing from the client is written into the SQL text.
+    fn push_filter(query: &mut QueryBuilder<'_, Postgres>, filter: &FilterExpr) {
+        match filter {
+            FilterExpr::And(left, right) | FilterExpr::Or(left, right) => {
+                query.push("(");
+                Self::push_filter(query, left);
+                query.push(if matches!(filter, FilterExpr::And(..)) { " AND " } else { " OR " });
+                Self::push_filter(query, right);
+                query.push(")");
+            }
+            FilterExpr::Not(inner) => {
+                query.push("NOT (");
+                Self::push_filter(query, inner);
+                query.push(")");
+            }
+            FilterExpr::Compare { column, op, value } => match (op.text_match(), value) {
+                (Some(text_match), SortValue::Text(text)) => {
+                    Self::push_text_match(query, column.name(), text, text_match);
+                }
+                _ => {
+                    query.push(column.name()).push(op.sql());
+                    Self::push_sort_value(query, value);
+                }
+            },
+            FilterExpr::In { column, values } => {
+                query.push(column.name()).push(" IN (");
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        query.push(", ");
+                    }
+                    Self::push_sort_value(query, value);
+                }
+                query.push(")");
+            }
+        }
+    }
+
+    // Trigram score of a fuzzy search (pg_trgm): how closely each term matches part of its
+    // columns, best column first. Every term has to match, so the weakest one sets the score.
+    fn push_fuzzy_score(query: &mut QueryBuilder<'_, Postgres>, params: &GoodsSearchParams) {
+        let terms = params.fuzzy_terms();
+        if terms.len() > 1 {
+            query.push("LEAST(");
+        }
+        for (i, (term, columns)) in terms.iter().enumerate() {
+            if i > 0 {
+                query.push(", ");
+            }
+            if columns.len() > 1 {
+                query.push("GREATEST(");
+            }
+            for (j, column) in columns.iter().enumerate() {
+                if j > 0 {
+                    query.push(", ");
+                }
+                query.push("word_similarity(").push_bind(term.to_string());
+                query.push(format!(", {})", column));
+            }
+            if columns.len() > 1 {
+                query.push(")");
+            }
+        }
+        if terms.len() > 1 {
+            query.push(")");
+        }
     }
 
     pub async fn verify_table_access(&self) -> Result<(), sqlx::Error> {
         // Since customer role only has SELECT permission, we should not try to create tables
+        // (writes go through the separate writer pool, see DatabaseConfig::writer)
         // The table should already exist and be created by an admin user
         // We'll just verify the table exists by doing a simple query
         
@@ -230,4 +1421,357 @@ impl GoodsTable {
             }
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::request::{extract_query_params, GOODS_QUERY_PARAMS};
+    use axum::extract::Query;
+
+    fn search_params(pairs: &[(&str, &str)]) -> GoodsSearchParams {
+        let pairs = pairs.iter().map(|(key, value)| (key.to_string(), value.to_string())).collect();
+        extract_query_params(Query(pairs), true, &GOODS_QUERY_PARAMS)
+            .unwrap()
+            .validate_and_parse()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_equivalent_searches_share_a_cache_key() {
+        let key = |pairs: &[(&str, &str)]| search_params(pairs).cache_key();
+        let base = key(&[("tag", "tea"), ("tag", "green"), ("max_price", "10"), ("fields", "goods_name,price")]);
+
+        // Tag and field order, trailing zeros and facets do not change the rows returned
+        let same = [
+            key(&[("tag", "green"), ("tag", "tea"), ("tag", "tea"), ("max_price", "10.00"), ("fields", "price,goods_name")]),
+            key(&[("tag", "tea"), ("tag", "green"), ("max_price", "10"), ("fields", "goods_name,price"), ("facets", "tag")]),
+        ];
+        assert!(same.iter().all(|other| *other == base));
+
+        let different = [
+            key(&[("tag", "tea"), ("max_price", "10"), ("fields", "goods_name,price")]),
+            key(&[("tag", "tea"), ("tag", "green"), ("max_price", "10.5"), ("fields", "goods_name,price")]),
+            key(&[("tag", "tea"), ("tag", "green"), ("max_price", "10"), ("fields", "goods_name")]),
+            key(&[("tag", "tea"), ("tag", "green"), ("max_price", "10"), ("fields", "goods_name,price"), ("offset", "20")]),
+            key(&[("tag", "tea"), ("tag", "green"), ("max_price", "10"), ("fields", "goods_name,price"), ("tag_mode", "any")]),
+        ];
+        assert!(different.iter().all(|other| *other != base));
+
+        let filtered = |filter: &str| key(&[("goods_name", "tea"), ("filter", filter)]);
+        assert_eq!(filtered("price lt 10 and not goods_name co \"x\""), filtered("price  lt 10 AND NOT goods_name co \"x\""));
+        assert_ne!(filtered("price lt 10"), filtered("price le 10"));
+        assert_ne!(filtered("price lt 10"), filtered("mass_g lt 10"));
+        assert_ne!(filtered("goods_name co \"x\""), filtered("goods_name co \"y\""));
+        assert_ne!(filtered("price lt 1 or price gt 2"), filtered("price lt 1 and price gt 2"));
+    }
+
+    #[test]
+    fn test_fuzzy_search_matches_every_term() {
+        let params = search_params(&[("q", "grean tea"), ("goods_name", "lose leaf"), ("fuzzy", "true")]);
+        let sql = GoodsTable::search_sql(&params);
+        assert!(sql.contains(
+            "LEAST(GREATEST(word_similarity($1, goods_name), word_similarity($2, material_code)), word_similarity($3, goods_name)) AS relevance"
+        ));
+        assert!(!sql.contains("websearch_to_tsquery") && !sql.contains("ILIKE"));
+
+        // Exact lookups are identifiers: they stay exact while the other terms match fuzzily
+        let params = search_params(&[
+            ("goods_name", "lose leaf"),
+            ("material_code", "TEA-1"),
+            ("material_code_match", "exact_cs"),
+            ("fuzzy", "true"),
+        ]);
+        assert_eq!(params.fuzzy_terms(), vec![("lose leaf", &["goods_name"][..])]);
+        let sql = GoodsTable::search_sql(&params);
+        assert!(sql.contains("word_similarity($2, goods_name) >= $3 AND material_code = $4"));
+
+        // With only exact lookups there is nothing to match fuzzily or to fall back to
+        let params = search_params(&[("material_code", "TEA-1"), ("material_code_match", "exact")]);
+        assert!(params.fuzzy_terms().is_empty());
+        let params = search_params(&[("material_code", "TEA-1"), ("material_code_match", "exact"), ("fuzzy", "true")]);
+        assert!(!params.is_fuzzy());
+        assert!(!GoodsTable::search_sql(&params).contains("word_similarity"));
+    }
+
+    #[test]
+    fn test_search_document_uses_the_text_search_config() {
+        let document = search_document();
+        assert!(document.contains(&format!("to_tsvector('{}', goods_name)", TEXT_SEARCH_CONFIG)));
+        assert!(document.contains(&format!("to_tsvector('{}', array_to_string(COALESCE(description, '{{}}'), ' '))", TEXT_SEARCH_CONFIG)));
+        assert_eq!(document.matches(TEXT_SEARCH_CONFIG).count(), 2);
+    }
+
+    #[test]
+    fn test_wildcard_keeps_the_other_filters() {
+        let params = search_params(&[
+            ("goods_name", "*"),
+            ("tag", "organic"),
+            ("filter", "price lt 10"),
+            ("q", "green tea"),
+            ("min_volumn_l", "1.5"),
+        ]);
+        let sql = GoodsTable::search_sql(&params);
+        assert!(sql.contains("FROM goods WHERE 1=1 AND volumn_l >= $2 AND description @> $3 AND price < $4 AND "));
+        assert!(sql.contains("@@ websearch_to_tsquery('english', $5)"));
+        assert!(!sql.contains("goods_name ILIKE"));
+
+        // The wildcard is not a term to match fuzzily, but q still is
+        let params = search_params(&[("material_code", "*"), ("q", "grean tea"), ("fuzzy", "true")]);
+        assert_eq!(params.fuzzy_terms(), vec![("grean tea", &["goods_name", "material_code"][..])]);
+
+        // On its own it lists the whole catalog
+        let params = search_params(&[("goods_name", "*")]);
+        assert!(GoodsTable::search_sql(&params).contains("FROM goods WHERE 1=1) AS goods"));
+    }
+
+    #[test]
+    fn test_facets_query() {
+        let sql = GoodsTable::facets_sql(&search_params(&[("goods_name", "*"), ("facets", "price,tag,mass_base")]));
+        assert!(sql.starts_with(
+            "WITH matched AS (SELECT goods_id, price, mass_g, volumn_l, mass_base, volumn_base, description FROM goods WHERE 1=1)"
+        ));
+        assert!(sql.contains("SELECT 'price'::text AS facet, width_bucket(price, $1::numeric[])::text AS value, COUNT(*) AS count"));
+        // A tag repeated in one description counts that good once, as in GET /goods/tags
+        assert!(sql.contains("COUNT(DISTINCT goods_id) AS count FROM matched CROSS JOIN LATERAL unnest(description) AS tag GROUP BY 2"));
+        assert_eq!(sql.matches(" UNION ALL ").count(), 2);
+
+        // Facets are computed over the same filters as the results
+        let sql = GoodsTable::facets_sql(&search_params(&[("max_price", "100"), ("facets", "mass_g")]));
+        assert!(sql.contains("FROM goods WHERE 1=1 AND price <= $1)"));
+    }
+
+    #[test]
+    fn test_facet_counts_collected() {
+        let rows: Vec<(String, String, i64)> = [
+            ("price", "0", 12),
+            ("price", "4", 40),
+            ("mass_base", "1000", 3),
+            ("mass_base", "1", 7),
+            ("tag", "frozen", 2),
+            ("tag", "organic", 9),
+        ]
+        .iter()
+        .map(|(facet, value, count)| (facet.to_string(), value.to_string(), *count))
+        .collect();
+        let facets = GoodsTable::collect_facets(&[Facet::Price, Facet::MassBase, Facet::Tag], &rows);
+
+        // Every price bucket is listed, empty ones included
+        let price = &facets["price"];
+        assert_eq!(price.len(), 6);
+        assert_eq!((price[0].min, price[0].max, price[0].count), (None, Some(rust_decimal::Decimal::from(10)), 12));
+        assert_eq!((price[1].count, price[4].count), (0, 40));
+        assert_eq!((price[5].min, price[5].max), (Some(rust_decimal::Decimal::from(1000)), None));
+
+        let mass_base: Vec<&str> = facets["mass_base"].iter().filter_map(|c| c.value.as_deref()).collect();
+        assert_eq!(mass_base, vec!["1", "1000"]);
+        assert_eq!(facets["tag"][0].value.as_deref(), Some("organic"));
+    }
+
+    #[test]
+    fn test_like_wildcards_are_escaped() {
+        assert_eq!(escape_like("A_1"), "A\\_1");
+        assert_eq!(escape_like("100%"), "100\\%");
+        assert_eq!(escape_like("a\\b"), "a\\\\b");
+
+        let params = search_params(&[
+            ("material_code", "A_1"),
+            ("material_code_match", "exact_cs"),
+            ("goods_name", "Cola"),
+            ("goods_name_match", "prefix"),
+        ]);
+        let sql = GoodsTable::search_sql(&params);
+        assert!(sql.contains("material_code = $"));
+        assert!(sql.contains("goods_name ILIKE $2 ESCAPE '\\'"));
+    }
+
+    #[test]
+    fn test_filter_compiles_to_parameterized_sql() {
+        let params = search_params(&[
+            ("max_price", "100"),
+            ("filter", "(price lt 10 or mass_g lt 50) and not goods_name co \"re%fill\" and material_code in (A, B)"),
+        ]);
+        let sql = GoodsTable::search_sql(&params);
+        assert!(sql.contains(
+            "AND price <= $1 AND (((price < $2 OR mass_g < $3) AND NOT (goods_name ILIKE $4 ESCAPE '\\')) AND material_code IN ($5, $6))"
+        ));
+        assert!(!sql.contains("re%fill"));
+    }
+
+    #[test]
+    fn test_stats_query() {
+        let sql = GoodsTable::stats_sql(&search_params(&[("tag", "organic")]));
+        assert!(sql.starts_with("SELECT COUNT(*) AS count, MIN(price) AS price_min, MAX(price) AS price_max"));
+        assert!(sql.contains("percentile_disc($1::float8[]) WITHIN GROUP (ORDER BY price) AS price_percentiles"));
+        assert!(sql.contains("ROUND((percentile_cont(0.5) WITHIN GROUP (ORDER BY volumn_l))::numeric, 4) AS volumn_l_median"));
+        assert!(sql.ends_with("FROM goods WHERE 1=1 AND description @> $4"));
+    }
+
+    #[test]
+    fn test_sparse_fieldset_reads_only_needed_columns() {
+        // The requested columns, plus the ones the sort and cursor need
+        let sql = GoodsTable::search_sql(&search_params(&[("max_price", "10"), ("fields", "goods_name,price"), ("sort", "-mass_g")]));
+        assert!(sql.starts_with("SELECT * FROM (SELECT goods_id, goods_name, price, mass_g FROM goods WHERE 1=1 AND price <= $1)"));
+    }
+
+    #[test]
+    fn test_export_reads_every_matching_row() {
+        let sql = GoodsTable::export_sql(&search_params(&[("min_price", "5"), ("sort", "-price"), ("fields", "goods_name")]));
+        assert_eq!(
+            sql,
+            "SELECT * FROM (SELECT goods_id, goods_name, price FROM goods WHERE 1=1 AND price >= $1) AS goods WHERE 1=1 ORDER BY price DESC, goods_id ASC"
+        );
+    }
+
+    // Database tests: each gets a scratch database from DATABASE_URL with the goods in
+    // db/test_goods.sql and db/setup.sql applied. Run them with `cargo test -- --ignored`.
+    #[sqlx::test(fixtures("../db/test_goods.sql", "../db/setup.sql"))]
+    #[ignore = "needs DATABASE_URL pointing at a Postgres server with pg_trgm"]
+    async fn test_cursor_pages_visit_every_good_once(pool: PgPool) {
+        let table = GoodsTable::new(pool.clone(), None);
+        let expected: Vec<i32> = sqlx::query_scalar("SELECT goods_id FROM goods ORDER BY mass_base DESC, price, goods_id")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+
+        // Pages of two, each starting after the cursor of the previous one
+        let mut visited = Vec::new();
+        let mut cursor: Option<String> = None;
+        loop {
+            let mut pairs = vec![("goods_name", "*"), ("sort", "-mass_base,price"), ("limit", "2")];
+            if let Some(cursor) = cursor.as_deref() {
+                pairs.push(("cursor", cursor));
+            }
+            let page = table.search(search_params(&pairs)).await.unwrap();
+            assert_eq!(page.total, 6);
+            visited.extend(page.goods.iter().map(|good| good.goods_id));
+            match page.goods.last() {
+                Some(last) if page.has_more => cursor = Some(crate::request::GoodsCursor::new(&page.sort, last).encode()),
+                _ => break,
+            }
+        }
+        assert_eq!(visited, expected);
+    }
+
+    #[sqlx::test(fixtures("../db/test_goods.sql", "../db/setup.sql"))]
+    #[ignore = "needs DATABASE_URL pointing at a Postgres server with pg_trgm"]
+    async fn test_suggestions_from_postgres(pool: PgPool) {
+        // One connection, so the last check runs on the connection the lookups used
+        let pool = sqlx::postgres::PgPoolOptions::new()
+            .max_connections(1)
+            .connect_with((*pool.connect_options()).clone())
+            .await
+            .unwrap();
+        let table = GoodsTable::new(pool.clone(), None);
+        let budget = Duration::from_millis(250);
+        let values = |suggestions: Vec<Suggestion>| suggestions.into_iter().map(|s| s.value).collect::<Vec<_>>();
+
+        // Names and codes of either case, shortest first
+        let suggestions = table.suggest("cOf", 10, budget).await.unwrap();
+        assert_eq!(suggestions[0], Suggestion { value: "COF-001".to_string(), field: "material_code".to_string() });
+        assert_eq!(values(suggestions), vec!["COF-001", "COF-002", "Coffee Beans", "Coffee Filters"]);
+        assert_eq!(values(table.suggest("c", 1, budget).await.unwrap()), vec!["COF-001"]);
+
+        // LIKE wildcards in the prefix match themselves
+        assert_eq!(values(table.suggest("100%", 10, budget).await.unwrap()), vec!["100% Water"]);
+        assert!(table.suggest("1_0", 10, budget).await.unwrap().is_empty());
+
+        // The statement timeout only lasted for the lookup's transaction
+        let timeout: String = sqlx::query_scalar("SHOW statement_timeout").fetch_one(&pool).await.unwrap();
+        assert_eq!(timeout, "0");
+    }
+
+    #[sqlx::test(fixtures("../db/test_goods.sql", "../db/setup.sql"))]
+    #[ignore = "needs DATABASE_URL pointing at a Postgres server with pg_trgm"]
+    async fn test_facets_from_postgres(pool: PgPool) {
+        let table = GoodsTable::new(pool, None);
+        let facets = table.facets(&search_params(&[("goods_name", "*"), ("facets", "price,tag,mass_base")])).await.unwrap();
+
+        let price: Vec<i64> = facets["price"].iter().map(|bucket| bucket.count).collect();
+        assert_eq!(price, vec![3, 2, 1, 0, 0, 0]);
+
+        // Green Tea lists "organic" twice but counts once
+        let tags: Vec<(&str, i64)> = facets["tag"].iter().map(|c| (c.value.as_deref().unwrap(), c.count)).collect();
+        assert_eq!(tags, vec![("organic", 2), ("tea", 2), ("fair-trade", 1), ("frozen", 1)]);
+
+        let mass_base: Vec<(&str, i64)> = facets["mass_base"].iter().map(|c| (c.value.as_deref().unwrap(), c.count)).collect();
+        assert_eq!(mass_base, vec![("1", 3), ("1000", 3)]);
+
+        // Only the goods matching the filters are counted
+        let facets = table.facets(&search_params(&[("max_price", "20"), ("facets", "price,tag")])).await.unwrap();
+        let price: Vec<i64> = facets["price"].iter().map(|bucket| bucket.count).collect();
+        assert_eq!(price, vec![3, 1, 0, 0, 0, 0]);
+        assert_eq!(facets["tag"].len(), 2);
+    }
+
+    #[sqlx::test(fixtures("../db/test_goods.sql", "../db/setup.sql"))]
+    #[ignore = "needs DATABASE_URL pointing at a Postgres server with pg_trgm"]
+    async fn test_stats_from_postgres(pool: PgPool) {
+        use rust_decimal::Decimal;
+        let table = GoodsTable::new(pool, None);
+
+        // Prices 1, 3, 4.5, 12, 25 and 60
+        let stats = table.stats(&search_params(&[("goods_name", "*")])).await.unwrap();
+        assert_eq!(stats.count, 6);
+        let price = &stats.columns["price"];
+        assert_eq!((price.min, price.max), (Some(Decimal::from(1)), Some(Decimal::from(60))));
+        assert_eq!(price.sum, Some(Decimal::new(1055, 1)));
+        assert_eq!(price.avg, Some(Decimal::new(175833, 4)));
+        // The median interpolates, percentiles are nearest-rank values
+        assert_eq!(price.median, Some(Decimal::new(825, 2)));
+        assert_eq!(price.percentiles["p10"], Decimal::from(1));
+        assert_eq!(price.percentiles["p50"], Decimal::new(45, 1));
+        assert_eq!(price.percentiles["p90"], Decimal::from(60));
+        assert_eq!(price.percentiles.len(), 7);
+        assert_eq!(stats.columns["mass_g"].max, Some(Decimal::from(2100)));
+
+        let stats = table.stats(&search_params(&[("tag", "organic")])).await.unwrap();
+        assert_eq!(stats.count, 2);
+        assert_eq!(stats.columns["price"].median, Some(Decimal::new(1475, 2)));
+
+        // Nothing matches: no values rather than zeros
+        let stats = table.stats(&search_params(&[("max_price", "0")])).await.unwrap();
+        assert_eq!(stats.count, 0);
+        assert_eq!(stats.columns["price"], ColumnStats::default());
+    }
+
+    #[sqlx::test(fixtures("../db/test_goods.sql", "../db/setup.sql"))]
+    #[ignore = "needs DATABASE_URL pointing at a Postgres server with pg_trgm"]
+    async fn test_filters_from_postgres(pool: PgPool) {
+        let table = GoodsTable::new(pool, None);
+        let codes = |page: GoodsPage| page.goods.into_iter().map(|good| good.material_code).collect::<Vec<_>>();
+
+        let filter = "(price lt 10 or mass_g ge 1000) and not goods_name co \"TEA\"";
+        let page = table.search(search_params(&[("filter", filter), ("sort", "material_code")])).await.unwrap();
+        assert_eq!(codes(page), vec!["COF-001", "COF-002", "JUI-001", "WAT-100"]);
+
+        let filter = "material_code in (TEA-002, JUI-001) or goods_name sw \"100%\"";
+        let page = table.search(search_params(&[("filter", filter), ("sort", "material_code")])).await.unwrap();
+        assert_eq!(codes(page), vec!["JUI-001", "TEA-002", "WAT-100"]);
+
+        // A % in the value is a literal character, not a wildcard
+        let page = table.search(search_params(&[("filter", "goods_name co \"%\"")])).await.unwrap();
+        assert_eq!(codes(page), vec!["WAT-100"]);
+
+        // The filter is ANDed with the other parameters
+        let page = table.search(search_params(&[("filter", "price lt 10"), ("tag", "organic")])).await.unwrap();
+        assert_eq!(codes(page), vec!["TEA-001"]);
+    }
+
+    #[sqlx::test(fixtures("../db/test_goods.sql", "../db/setup.sql"))]
+    #[ignore = "needs DATABASE_URL pointing at a Postgres server with pg_trgm"]
+    async fn test_export_from_postgres(pool: PgPool) {
+        let table = GoodsTable::new(pool, None);
+
+        let params = search_params(&[("min_price", "5"), ("sort", "-price"), ("fields", "goods_name")]);
+        let goods: Vec<Good> = table.export(params).try_collect().await.unwrap();
+        let names: Vec<&str> = goods.iter().map(|good| good.goods_name.as_str()).collect();
+        assert_eq!(names, vec!["Orange Juice", "Coffee Beans", "Black Tea"]);
+        // Only the requested columns and the ones the sort needs are read
+        assert_eq!(goods[0].price, rust_decimal::Decimal::from(60));
+        assert!(goods.iter().all(|good| good.material_code.is_empty() && good.description.is_none()));
+
+        let goods: Vec<Good> = table.export(search_params(&[("goods_name", "*")])).try_collect().await.unwrap();
+        assert_eq!(goods.len(), 6);
+    }
+}
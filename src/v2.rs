@@ -0,0 +1,575 @@
+// src/v2.rs
+//
+// Version 2 of the goods API, mounted under /v2. It behaves exactly like the
+// unversioned v1 routes but fixes the public contract: volume is spelled
+// `volume_l` / `volume_base` (v1: `volumn_l` / `volumn_base`) in responses, query
+// parameters, sort keys and request bodies, and `description` is always an array.
+// Requests are translated to the v1 search model, so both versions share one
+// implementation and return the same rows.
+use crate::auth::authorized;
+use crate::config::CacheControlConfig;
+use crate::error::{AppError, FieldError};
+use crate::format::{format_param, ResponseFormat};
+use crate::http_cache::cached;
+use crate::request::{
+    check_known_params, deserialize_present, extract_query_params, GoodPatchRequest,
+    GoodWriteRequest, GoodsBatchRequest, GoodsQueryParams, GoodsSearchRequest, FuzzyFlag,
+    SuggestQueryParams, EXPORT_QUERY_PARAMS, FILTERABLE_COLUMNS, FILTER_QUERY_PARAMS,
+    GOODS_QUERY_PARAMS,
+};
+use crate::response::{
+    created_response, select_fields, select_item, success_response, ApiResponse,
+    BatchLookupResponse,
+};
+use crate::server::{
+    apply_good_patch, batch_lookup, find_good_by_id, find_good_by_material_code, insert_good,
+    compute_stats, list_tags, remove_good, replace_good, search_goods, stream_goods, suggest_goods,
+    AppState, GoodsSearchResult,
+};
+use crate::tables::Good;
+use axum::{
+    extract::{rejection::JsonRejection, Path, Query, State},
+    http::HeaderMap,
+    response::Response,
+    routing::{get, post, put},
+    Json, Router,
+};
+use futures::StreamExt;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tracing::{info, warn};
+
+pub fn router(cache_control: &CacheControlConfig, admin_token: Option<&str>) -> Router<AppState> {
+    Router::new()
+        .route(
+            "/goods",
+            cached(get(get_goods), &cache_control.goods).merge(authorized(post(create_good), admin_token)),
+        )
+        .route("/goods/batch", post(get_goods_batch))
+        .route("/goods/search", post(post_goods_search))
+        .route("/goods/suggest", cached(get(get_goods_suggestions), &cache_control.suggest))
+        .route("/goods/tags", cached(get(get_goods_tags), &cache_control.tags))
+        .route("/goods/stats", cached(get(get_goods_stats), &cache_control.stats))
+        .route("/goods/export", cached(get(export_goods), &cache_control.export))
+        .route(
+            "/goods/{goods_id}",
+            cached(get(get_good_by_id), &cache_control.lookup).merge(authorized(
+                put(update_good).patch(patch_good).delete(delete_good),
+                admin_token,
+            )),
+        )
+        .route(
+            "/goods/by-material/{material_code}",
+            cached(get(get_good_by_material_code), &cache_control.lookup),
+        )
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GoodV2 {
+    pub goods_id: i32,
+    pub material_code: String,
+    pub goods_name: String,
+    pub description: Vec<String>,
+    pub price: Decimal,
+    pub volume_l: Decimal,
+    pub mass_g: Decimal,
+    pub mass_base: i16,
+    pub volume_base: i16,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub relevance: Option<f32>,
+}
+
+impl From<Good> for GoodV2 {
+    fn from(good: Good) -> Self {
+        Self {
+            goods_id: good.goods_id,
+            material_code: good.material_code,
+            goods_name: good.goods_name,
+            description: good.description.unwrap_or_default(),
+            price: good.price,
+            volume_l: good.volumn_l,
+            mass_g: good.mass_g,
+            mass_base: good.mass_base,
+            volume_base: good.volumn_base,
+            relevance: good.relevance,
+        }
+    }
+}
+
+// Body of POST /v2/goods and PUT /v2/goods/{goods_id}
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct GoodWriteRequestV2 {
+    pub material_code: String,
+    pub goods_name: String,
+    #[serde(default)]
+    pub description: Option<Vec<String>>,
+    pub price: Decimal,
+    pub volume_l: Decimal,
+    pub mass_g: Decimal,
+    pub mass_base: i16,
+    pub volume_base: i16,
+}
+
+impl From<GoodWriteRequestV2> for GoodWriteRequest {
+    fn from(request: GoodWriteRequestV2) -> Self {
+        Self {
+            material_code: request.material_code,
+            goods_name: request.goods_name,
+            description: request.description,
+            price: request.price,
+            volumn_l: request.volume_l,
+            mass_g: request.mass_g,
+            mass_base: request.mass_base,
+            volumn_base: request.volume_base,
+        }
+    }
+}
+
+// Body of PATCH /v2/goods/{goods_id}
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct GoodPatchRequestV2 {
+    pub material_code: Option<String>,
+    pub goods_name: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_present")]
+    pub description: Option<Option<Vec<String>>>,
+    pub price: Option<Decimal>,
+    pub volume_l: Option<Decimal>,
+    pub mass_g: Option<Decimal>,
+    pub mass_base: Option<i16>,
+    pub volume_base: Option<i16>,
+}
+
+impl From<GoodPatchRequestV2> for GoodPatchRequest {
+    fn from(request: GoodPatchRequestV2) -> Self {
+        Self {
+            material_code: request.material_code,
+            goods_name: request.goods_name,
+            description: request.description,
+            price: request.price,
+            volumn_l: request.volume_l,
+            mass_g: request.mass_g,
+            mass_base: request.mass_base,
+            volumn_base: request.volume_base,
+        }
+    }
+}
+
+// Filters of a POST /v2/goods/search document, named like the /v2/goods query parameters
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct SearchFiltersV2 {
+    pub goods_id: Option<i64>,
+    pub material_code: Option<String>,
+    pub goods_name: Option<String>,
+    pub material_code_match: Option<String>,
+    pub goods_name_match: Option<String>,
+    pub price: Option<Decimal>,
+    pub volume_l: Option<Decimal>,
+    pub mass_g: Option<Decimal>,
+    pub min_volume_l: Option<Decimal>,
+    pub max_volume_l: Option<Decimal>,
+    pub min_mass_g: Option<Decimal>,
+    pub max_mass_g: Option<Decimal>,
+    pub min_price: Option<Decimal>,
+    pub max_price: Option<Decimal>,
+    pub q: Option<String>,
+    pub fuzzy: Option<FuzzyFlag>,
+    #[serde(default)]
+    pub tag: Vec<String>,
+    pub tag_mode: Option<String>,
+    pub filter: Option<String>,
+}
+
+// v2 spelling of a v1 parameter or column name
+fn v2_name(v1: &str) -> String {
+    v1.replace("volumn", "volume")
+}
+
+// v1 spelling of a v2 parameter or column name
+fn v1_name(v2: &str) -> String {
+    v2.replace("volume", "volumn")
+}
+
+// Check v2 query parameters against the v2 spelling of `v1_valid` and translate them to
+// the v1 names the search model uses
+pub fn translate_query_params(
+    params: Vec<(String, String)>,
+    strict: bool,
+    v1_valid: &[&str],
+) -> Result<GoodsQueryParams, AppError> {
+    let valid: Vec<String> = v1_valid.iter().map(|name| v2_name(name)).collect();
+    let valid: Vec<&str> = valid.iter().map(String::as_str).collect();
+    if strict {
+        check_known_params(&params.iter().cloned().collect(), &valid)?;
+    }
+
+    let mut translated = Vec::new();
+    for (key, value) in params {
+        // Outside strict mode unknown keys, including the old v1 spellings, are ignored
+        if !valid.contains(&key.as_str()) {
+            continue;
+        }
+        // Sort keys, facets, fields and filter expressions name columns, which need
+        // translating too
+        let value = match key.as_str() {
+            "sort" | "facets" | "fields" => translate_columns(&key, &value)?,
+            "filter" => translate_filter(&value)?,
+            _ => value,
+        };
+        translated.push((v1_name(&key), value));
+    }
+
+    extract_query_params(Query(translated), false, v1_valid)
+}
+
+fn translate_columns(field: &str, columns: &str) -> Result<String, AppError> {
+    if columns.contains("volumn") {
+        return Err(v1_spelling_error(field, columns));
+    }
+    Ok(v1_name(columns))
+}
+
+// Rename the columns of a filter expression, leaving quoted text and other words alone.
+// Both spellings have the same length, so error positions still match the v2 input.
+fn translate_filter(filter: &str) -> Result<String, AppError> {
+    let mut translated = String::with_capacity(filter.len());
+    let mut word = String::new();
+    let mut quote = None;
+    let mut escaped = false;
+
+    let flush = |translated: &mut String, word: &mut String| -> Result<(), AppError> {
+        if FILTERABLE_COLUMNS.contains(&word.as_str()) && word.contains("volumn") {
+            return Err(v1_spelling_error("filter", filter));
+        }
+        let v1 = v1_name(word);
+        translated.push_str(if FILTERABLE_COLUMNS.contains(&v1.as_str()) { &v1 } else { word });
+        word.clear();
+        Ok(())
+    };
+
+    for c in filter.chars() {
+        match quote {
+            Some(q) => {
+                if escaped {
+                    escaped = false;
+                } else if c == '\\' {
+                    escaped = true;
+                } else if c == q {
+                    quote = None;
+                }
+                translated.push(c);
+            }
+            None if c.is_alphanumeric() || c == '_' => word.push(c),
+            None => {
+                flush(&mut translated, &mut word)?;
+                if c == '"' || c == '\'' {
+                    quote = Some(c);
+                }
+                translated.push(c);
+            }
+        }
+    }
+    flush(&mut translated, &mut word)?;
+
+    Ok(translated)
+}
+
+fn v1_spelling_error(field: &str, value: &str) -> AppError {
+    AppError::InvalidFields(vec![FieldError {
+        field: field.to_string(),
+        code: "invalid_value".to_string(),
+        message: "v2 spells volume as volume_l and volume_base".to_string(),
+        received_value: Some(value.to_string()),
+    }])
+}
+
+// Report errors raised by the shared v1 implementation with v2 names
+fn to_v2_error(error: AppError) -> AppError {
+    match error {
+        AppError::Validation(message) => AppError::Validation(v2_name(&message)),
+        AppError::InvalidFields(errors) => AppError::InvalidFields(
+            errors
+                .into_iter()
+                .map(|error| FieldError {
+                    field: v2_name(&error.field),
+                    message: v2_name(&error.message),
+                    ..error
+                })
+                .collect(),
+        ),
+        other => other,
+    }
+}
+
+// Route: GET /v2/goods - Get goods with query parameters
+async fn get_goods(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    query: Query<Vec<(String, String)>>,
+) -> Result<Response, AppError> {
+    info!("v2 goods search requested with params: {:?}", query.0);
+    let format = ResponseFormat::negotiate(&headers, format_param(&query.0))?;
+
+    let query_params = translate_query_params(query.0, state.config.server.strict_query_params, &GOODS_QUERY_PARAMS)
+        .inspect_err(|error| warn!("Rejected v2 query parameters: {}", error))?;
+    let result = search_goods(&state, query_params).await.map_err(to_v2_error)?;
+    search_response(result, format)
+}
+
+// Route: POST /v2/goods/search - Search goods with a JSON document instead of query parameters
+async fn post_goods_search(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    query: Query<Vec<(String, String)>>,
+    body: Result<Json<GoodsSearchRequest<SearchFiltersV2>>, JsonRejection>,
+) -> Result<Response, AppError> {
+    let format = ResponseFormat::negotiate(&headers, format_param(&query.0))?;
+    let Json(request) = body?;
+    info!("v2 goods search requested with document: {:?}", request);
+
+    let query_params = translate_query_params(request.into_query_pairs(), true, &GOODS_QUERY_PARAMS)
+        .inspect_err(|error| warn!("Rejected v2 search document: {}", error))?;
+
+    let result = search_goods(&state, query_params).await.map_err(to_v2_error)?;
+    search_response(result, format)
+}
+
+fn search_response(result: GoodsSearchResult, format: ResponseFormat) -> Result<Response, AppError> {
+    let fields: Vec<String> = result.fields.iter().map(|column| v2_name(column.name())).collect();
+    let columns: Vec<String> = result.columns().iter().map(|column| v2_name(column.name())).collect();
+    let goods: Vec<GoodV2> = result.goods.into_iter().map(GoodV2::from).collect();
+    let facets = result
+        .facets
+        .map(|facets| facets.into_iter().map(|(name, counts)| (v2_name(&name), counts)).collect());
+    let envelope = ApiResponse::success(select_fields(goods, &fields), "Goods retrieved successfully")
+        .with_pagination(result.pagination)
+        .with_search(result.search)
+        .with_facets(facets);
+    format.respond(envelope, &columns)
+}
+
+// Route: GET /v2/goods/suggest - Typeahead suggestions, identical to v1
+async fn get_goods_suggestions(
+    State(state): State<AppState>,
+    query: Query<HashMap<String, String>>,
+) -> Result<Response, AppError> {
+    let params = SuggestQueryParams::from_query(query.0, state.config.server.strict_query_params)
+        .inspect_err(|error| warn!("Rejected v2 suggest parameters: {}", error))?;
+    let suggestions = suggest_goods(&state, params).await?;
+    Ok(success_response(suggestions, "Suggestions retrieved successfully"))
+}
+
+// Route: GET /v2/goods/tags - Description tags with usage counts, identical to v1
+async fn get_goods_tags(State(state): State<AppState>) -> Result<Response, AppError> {
+    let tags = list_tags(&state).await?;
+    Ok(success_response(tags, "Tags retrieved successfully"))
+}
+
+// Route: GET /v2/goods/stats - Summary statistics of the goods matching the /v2/goods filters
+async fn get_goods_stats(
+    State(state): State<AppState>,
+    query: Query<Vec<(String, String)>>,
+) -> Result<Response, AppError> {
+    info!("v2 goods statistics requested with params: {:?}", query.0);
+
+    let query_params = translate_query_params(query.0, state.config.server.strict_query_params, &FILTER_QUERY_PARAMS)
+        .inspect_err(|error| warn!("Rejected v2 stats parameters: {}", error))?;
+    let mut stats = compute_stats(&state, query_params).await.map_err(to_v2_error)?;
+    stats.columns = stats.columns.into_iter().map(|(name, column)| (v2_name(&name), column)).collect();
+    Ok(success_response(stats, "Statistics computed successfully"))
+}
+
+// Route: GET /v2/goods/export - Every good matching the /v2/goods filters, streamed as a
+// JSON array or NDJSON
+async fn export_goods(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    query: Query<Vec<(String, String)>>,
+) -> Result<Response, AppError> {
+    info!("v2 goods export requested with params: {:?}", query.0);
+    let format = ResponseFormat::negotiate(&headers, format_param(&query.0))?.streamable()?;
+
+    let query_params = translate_query_params(query.0, state.config.server.strict_query_params, &EXPORT_QUERY_PARAMS)
+        .inspect_err(|error| warn!("Rejected v2 export parameters: {}", error))?;
+    let (goods, fields) = stream_goods(&state, query_params).await.map_err(to_v2_error)?;
+    let fields: Vec<String> = fields.iter().map(|column| v2_name(column.name())).collect();
+    format.stream(goods.map(move |good| good.map(|good| select_item(GoodV2::from(good), &fields))))
+}
+
+// Route: GET /v2/goods/{goods_id} - Get a single good by id
+async fn get_good_by_id(
+    State(state): State<AppState>,
+    Path(goods_id): Path<String>,
+) -> Result<Response, AppError> {
+    let good = find_good_by_id(&state, &goods_id).await?;
+    Ok(success_response(GoodV2::from(good), "Good retrieved successfully"))
+}
+
+// Route: GET /v2/goods/by-material/{material_code} - Get a single good by exact material code
+async fn get_good_by_material_code(
+    State(state): State<AppState>,
+    Path(material_code): Path<String>,
+) -> Result<Response, AppError> {
+    let good = find_good_by_material_code(&state, &material_code).await?;
+    Ok(success_response(GoodV2::from(good), "Good retrieved successfully"))
+}
+
+// Route: POST /v2/goods/batch - Resolve many goods by ids and/or material codes at once
+async fn get_goods_batch(
+    State(state): State<AppState>,
+    body: Result<Json<GoodsBatchRequest>, JsonRejection>,
+) -> Result<Response, AppError> {
+    let Json(request) = body?;
+    let result = batch_lookup(&state, request).await?;
+
+    let result = BatchLookupResponse {
+        goods: result.goods.into_iter().map(GoodV2::from).collect::<Vec<_>>(),
+        not_found: result.not_found,
+    };
+    Ok(success_response(result, "Goods retrieved successfully"))
+}
+
+// Route: POST /v2/goods - Create a good
+async fn create_good(
+    State(state): State<AppState>,
+    body: Result<Json<GoodWriteRequestV2>, JsonRejection>,
+) -> Result<Response, AppError> {
+    let Json(request) = body?;
+    let good = insert_good(&state, request.into()).await.map_err(to_v2_error)?;
+    Ok(created_response(GoodV2::from(good), "Good created successfully"))
+}
+
+// Route: PUT /v2/goods/{goods_id} - Replace a good
+async fn update_good(
+    State(state): State<AppState>,
+    Path(goods_id): Path<String>,
+    body: Result<Json<GoodWriteRequestV2>, JsonRejection>,
+) -> Result<Response, AppError> {
+    let Json(request) = body?;
+    let good = replace_good(&state, &goods_id, request.into()).await.map_err(to_v2_error)?;
+    Ok(success_response(GoodV2::from(good), "Good updated successfully"))
+}
+
+// Route: PATCH /v2/goods/{goods_id} - Update some fields of a good
+async fn patch_good(
+    State(state): State<AppState>,
+    Path(goods_id): Path<String>,
+    body: Result<Json<GoodPatchRequestV2>, JsonRejection>,
+) -> Result<Response, AppError> {
+    let Json(request) = body?;
+    let good = apply_good_patch(&state, &goods_id, request.into()).await.map_err(to_v2_error)?;
+    Ok(success_response(GoodV2::from(good), "Good updated successfully"))
+}
+
+// Route: DELETE /v2/goods/{goods_id} - Delete a good
+async fn delete_good(
+    State(state): State<AppState>,
+    Path(goods_id): Path<String>,
+) -> Result<Response, AppError> {
+    let good = remove_good(&state, &goods_id).await?;
+    Ok(success_response(GoodV2::from(good), "Good deleted successfully"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_query_params_are_translated() {
+        let query_params = translate_query_params(
+            params(&[("goods_name", "*"), ("min_volume_l", "1.5"), ("sort", "-volume_l,volume_base")]),
+            true,
+            &GOODS_QUERY_PARAMS,
+        )
+        .unwrap();
+        assert_eq!(query_params.min_volumn_l.as_deref(), Some("1.5"));
+        assert_eq!(query_params.sort.as_deref(), Some("-volumn_l,volumn_base"));
+    }
+
+    #[test]
+    fn test_v1_spellings_are_rejected() {
+        let errors = match translate_query_params(params(&[("volumn_l", "2")]), true, &GOODS_QUERY_PARAMS) {
+            Err(AppError::InvalidFields(errors)) => errors,
+            other => panic!("expected field errors, got {:?}", other),
+        };
+        assert_eq!(errors[0].field, "volumn_l");
+        assert!(errors[0].message.contains("Did you mean 'volume_l'?"));
+
+        assert!(translate_query_params(params(&[("goods_name", "*"), ("sort", "volumn_l")]), true, &GOODS_QUERY_PARAMS).is_err());
+    }
+
+    #[test]
+    fn test_errors_use_v2_names() {
+        let query_params = translate_query_params(params(&[("min_volume_l", "abc")]), true, &GOODS_QUERY_PARAMS).unwrap();
+        let error = to_v2_error(query_params.validate_and_parse().unwrap_err());
+        match error {
+            AppError::InvalidFields(errors) => {
+                assert_eq!(errors[0].field, "min_volume_l");
+                assert!(!errors[0].message.contains("volumn"));
+            }
+            other => panic!("expected field errors, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_good_uses_v2_field_names() {
+        let good = GoodV2::from(Good {
+            volumn_l: Decimal::new(15, 1),
+            volumn_base: 1000,
+            ..Default::default()
+        });
+        let json = serde_json::to_value(&good).unwrap();
+        assert_eq!(json["volume_l"], "1.5");
+        assert_eq!(json["volume_base"], 1000);
+        assert_eq!(json["description"], serde_json::json!([]));
+        assert!(json.get("volumn_l").is_none());
+    }
+
+    #[test]
+    fn test_stats_accept_only_filters() {
+        let query_params = translate_query_params(params(&[("max_volume_l", "2")]), true, &FILTER_QUERY_PARAMS).unwrap();
+        assert_eq!(query_params.max_volumn_l.as_deref(), Some("2"));
+
+        // Paging and sorting mean nothing for statistics
+        assert!(translate_query_params(params(&[("sort", "price")]), true, &FILTER_QUERY_PARAMS).is_err());
+    }
+
+    #[test]
+    fn test_filter_columns_are_translated() {
+        assert_eq!(
+            translate_filter("volume_l lt 2 or goods_name co \"volume_l pack\"").unwrap(),
+            "volumn_l lt 2 or goods_name co \"volume_l pack\""
+        );
+        assert_eq!(translate_filter("goods_name eq volume").unwrap(), "goods_name eq volume");
+        assert!(translate_filter("volumn_base eq 1").is_err());
+    }
+
+    #[test]
+    fn test_search_document_uses_v2_names() {
+        let document: GoodsSearchRequest<SearchFiltersV2> = serde_json::from_str(
+            r#"{"filters": {"max_volume_l": 2, "filter": "volume_l gt 1"}, "sort": ["-volume_l"], "facets": ["volume_base"]}"#,
+        )
+        .unwrap();
+        let params = translate_query_params(document.into_query_pairs(), true, &GOODS_QUERY_PARAMS).unwrap();
+        assert_eq!(params.max_volumn_l.as_deref(), Some("2"));
+        assert_eq!(params.filter.as_deref(), Some("volumn_l gt 1"));
+        assert_eq!(params.sort.as_deref(), Some("-volumn_l"));
+        assert_eq!(params.facets.as_deref(), Some("volumn_base"));
+
+        // The v1 spellings are not part of the v2 document
+        assert!(serde_json::from_str::<GoodsSearchRequest<SearchFiltersV2>>(r#"{"filters": {"volumn_l": 1}}"#).is_err());
+        let document: GoodsSearchRequest<SearchFiltersV2> =
+            serde_json::from_str(r#"{"filters": {"q": "tea"}, "sort": ["volumn_l"]}"#).unwrap();
+        assert!(translate_query_params(document.into_query_pairs(), true, &GOODS_QUERY_PARAMS).is_err());
+    }
+}